@@ -0,0 +1,256 @@
+use crate::config::ResponseType;
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The value that replaces redacted header and query parameter values.
+pub(crate) const REDACTED: &str = "REDACTED";
+
+/// Headers that are redacted from access log entries by default.
+pub(crate) const DEFAULT_REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "x-api-key",
+];
+
+/// The output format of a [StdoutAccessLog](StdoutAccessLog).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessLogFormat {
+    /// One JSON object per line, which can be queried with CloudWatch Logs Insights.
+    Json,
+    /// The Common Log Format used by web servers such as Apache and nginx.
+    Common,
+}
+
+/// A record of a single request processed by a [RocketHandler](crate::RocketHandler), logged
+/// when access logging is enabled with
+/// [access_log](crate::RocketHandlerBuilder::access_log).
+///
+/// Sensitive header and query parameter values have already been redacted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessLogEntry {
+    /// The AWS request ID of the Lambda invocation.
+    pub request_id: String,
+    /// The time at which the invocation started processing the request.
+    pub time: SystemTime,
+    /// The IP address of the client, if known.
+    pub remote_addr: Option<String>,
+    /// The HTTP method of the request.
+    pub method: String,
+    /// The path and query string processed by Rocket.
+    pub path: String,
+    /// The path of the request as received by API Gateway or the load balancer.
+    pub original_path: String,
+    /// The API Gateway base path of the request, which is empty if there is none.
+    pub base_path: String,
+    /// The request headers.
+    pub headers: Vec<(String, String)>,
+    /// The status code of the response.
+    pub status: u16,
+    /// How the response body was encoded, or `None` if the response has no body.
+    pub response_type: Option<ResponseType>,
+    /// The size of the request body in bytes.
+    pub request_body_size: usize,
+    /// The size of the response body in bytes, before any base-64 encoding.
+    pub response_body_size: usize,
+    /// Whether this was the first request processed by the handler.
+    pub cold_start: bool,
+    /// The time taken to process the request.
+    pub duration: Duration,
+}
+
+impl AccessLogEntry {
+    /// Formats the entry as a JSON object.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{AccessLogEntry, ResponseType};
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let entry = AccessLogEntry {
+    ///     request_id: "abc".to_owned(),
+    ///     time: UNIX_EPOCH,
+    ///     remote_addr: None,
+    ///     method: "GET".to_owned(),
+    ///     path: "/path".to_owned(),
+    ///     original_path: "/path".to_owned(),
+    ///     base_path: String::new(),
+    ///     headers: Vec::new(),
+    ///     status: 200,
+    ///     response_type: Some(ResponseType::Text),
+    ///     request_body_size: 0,
+    ///     response_body_size: 5,
+    ///     cold_start: true,
+    ///     duration: Duration::from_millis(3),
+    /// };
+    /// assert_eq!(entry.to_json()["status"], 200);
+    /// assert_eq!(entry.to_json()["response_type"], "Text");
+    /// ```
+    pub fn to_json(&self) -> Value {
+        let headers: serde_json::Map<String, Value> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+            .collect();
+        json!({
+            "request_id": self.request_id,
+            "time": format_epoch_millis(self.time),
+            "remote_addr": self.remote_addr,
+            "method": self.method,
+            "path": self.path,
+            "original_path": self.original_path,
+            "base_path": self.base_path,
+            "headers": headers,
+            "status": self.status,
+            "response_type": self.response_type.map(|t| format!("{:?}", t)),
+            "request_body_size": self.request_body_size,
+            "response_body_size": self.response_body_size,
+            "cold_start": self.cold_start,
+            "duration_ms": self.duration.as_secs_f64() * 1000.0,
+        })
+    }
+
+    /// Formats the entry as a line in the Common Log Format.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::AccessLogEntry;
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let entry = AccessLogEntry {
+    ///     request_id: "abc".to_owned(),
+    ///     time: UNIX_EPOCH + Duration::from_secs(971_185_336),
+    ///     remote_addr: Some("1.2.3.4".to_owned()),
+    ///     method: "GET".to_owned(),
+    ///     path: "/path".to_owned(),
+    ///     original_path: "/path".to_owned(),
+    ///     base_path: String::new(),
+    ///     headers: Vec::new(),
+    ///     status: 200,
+    ///     response_type: None,
+    ///     request_body_size: 0,
+    ///     response_body_size: 0,
+    ///     cold_start: false,
+    ///     duration: Duration::from_millis(3),
+    /// };
+    /// assert_eq!(
+    ///     entry.to_common_log(),
+    ///     r#"1.2.3.4 - - [10/Oct/2000:13:42:16 +0000] "GET /path HTTP/1.1" 200 -"#
+    /// );
+    /// ```
+    pub fn to_common_log(&self) -> String {
+        let size = if self.response_body_size == 0 {
+            "-".to_owned()
+        } else {
+            self.response_body_size.to_string()
+        };
+        format!(
+            "{} - - [{}] \"{} {} HTTP/1.1\" {} {}",
+            self.remote_addr.as_ref().map(String::as_str).unwrap_or("-"),
+            format_common_log_time(self.time),
+            self.method,
+            self.path,
+            self.status,
+            size
+        )
+    }
+}
+
+/// A destination for access log entries.
+pub trait AccessLogSink: Send + Sync {
+    /// Records an access log entry.
+    fn log(&self, entry: &AccessLogEntry);
+}
+
+/// An `AccessLogSink` that writes one line per entry to stdout, which Lambda sends to CloudWatch Logs.
+///
+/// # Example
+///
+/// ```rust
+/// use rocket_lamb::{AccessLogFormat, RocketExt, StdoutAccessLog};
+///
+/// let builder = rocket::ignite()
+///     .lambda()
+///     .access_log(StdoutAccessLog(AccessLogFormat::Json));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StdoutAccessLog(pub AccessLogFormat);
+
+impl AccessLogSink for StdoutAccessLog {
+    fn log(&self, entry: &AccessLogEntry) {
+        match self.0 {
+            AccessLogFormat::Json => println!("{}", entry.to_json()),
+            AccessLogFormat::Common => println!("{}", entry.to_common_log()),
+        }
+    }
+}
+
+impl<S: AccessLogSink + ?Sized> AccessLogSink for std::sync::Arc<S> {
+    fn log(&self, entry: &AccessLogEntry) {
+        (**self).log(entry)
+    }
+}
+
+/// Replaces the values of the given query parameters in a path and query string.
+pub(crate) fn redact_query(uri: &str, parameters: &[String]) -> String {
+    let (path, query) = match uri.find('?') {
+        Some(index) => (&uri[..index], &uri[index + 1..]),
+        None => return uri.to_owned(),
+    };
+    let pairs: Vec<String> = query
+        .split('&')
+        .map(|pair| {
+            let key = pair.split('=').next().unwrap_or_default();
+            if pair.contains('=') && parameters.iter().any(|p| p == key) {
+                format!("{}={}", key, REDACTED)
+            } else {
+                pair.to_owned()
+            }
+        })
+        .collect();
+    format!("{}?{}", path, pairs.join("&"))
+}
+
+fn format_epoch_millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
+
+/// Formats a time as `10/Oct/2000:13:55:36 +0000`.
+fn format_common_log_time(time: SystemTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs_of_day = secs % 86400;
+    format!(
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+/// Converts a number of days since the Unix epoch into a (year, month, day) date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,41 @@
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request};
+
+/// Request guard that succeeds for Application Load Balancer health check requests, and
+/// forwards for all other requests.
+///
+/// Health checks are sent to the target group's configured health check path like any other
+/// request, so this allows a route to respond to them without doing any further work.
+///
+/// # Example
+///
+/// ```rust
+/// #![feature(proc_macro_hygiene, decl_macro)]
+/// # #[macro_use] extern crate rocket;
+/// use rocket_lamb::AlbHealthCheck;
+///
+/// #[get("/")]
+/// fn health_check(_health_check: AlbHealthCheck) -> &'static str {
+///     "OK"
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AlbHealthCheck;
+
+impl<'a, 'r> FromRequest<'a, 'r> for AlbHealthCheck {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let is_health_check = request
+            .headers()
+            .get_one("User-Agent")
+            .map(|ua| ua.starts_with("ELB-HealthChecker/"))
+            .unwrap_or(false);
+        if is_health_check {
+            Outcome::Success(AlbHealthCheck)
+        } else {
+            Outcome::Forward(())
+        }
+    }
+}
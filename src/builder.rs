@@ -1,12 +1,28 @@
+use crate::access_log::AccessLogSink;
+use crate::cli;
 use crate::config::*;
+use crate::config_source::{self, CodeSettings, ConfigError};
 use crate::handler::{LazyClient, RocketHandler};
-use lambda_http::lambda;
+use crate::metrics::EmfMetrics;
+use crate::openapi;
+use crate::otel::SpanExporter;
+use crate::params::StageVariables;
+use crate::recording::RecordingSink;
+use crate::sam::{self, SamEventSource, SamRoutes};
+use crate::xray::XRayEmitter;
+use lambda_http::{lambda, Body, Request, Response};
+use lambda_runtime::Context;
 use rocket::Rocket;
+use serde_json::Value;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// A builder to create and configure a [RocketHandler](RocketHandler).
 pub struct RocketHandlerBuilder {
     rocket: Rocket,
     config: Config,
+    code_settings: CodeSettings,
 }
 
 impl RocketHandlerBuilder {
@@ -23,12 +39,15 @@ impl RocketHandlerBuilder {
         RocketHandlerBuilder {
             rocket,
             config: Config::default(),
+            code_settings: CodeSettings::default(),
         }
     }
 
     /// Creates a new `RocketHandler` from an instance of `Rocket`, which can be passed to the [lambda_http::lambda!](lambda_http::lambda) macro.
     ///
-    /// Alternatively, you can use the [launch()](RocketHandlerBuilder::launch) method.
+    /// To also handle events from API Gateway WebSocket APIs, VPC Lattice, Lambda@Edge or direct invocations, pass it to [lambda_runtime::start](lambda_runtime::start) instead.
+    ///
+    /// Alternatively, you can use the [launch()](RocketHandlerBuilder::launch) or [launch_raw()](RocketHandlerBuilder::launch_raw) methods.
     ///
     /// # Example
     ///
@@ -43,11 +62,18 @@ impl RocketHandlerBuilder {
         RocketHandler {
             client: LazyClient::Uninitialized(self.rocket),
             config: self.config,
+            context: Context::default(),
+            overrunning: None,
+            current_route: Arc::new(Mutex::new(None)),
         }
     }
 
     /// Starts handling Lambda events by polling for events using Lambda's Runtime APIs.
     ///
+    /// This handles API Gateway and Application Load Balancer requests with the
+    /// [lambda_http::lambda!](lambda_http::lambda) macro. To also handle other events, use
+    /// [launch_raw()](RocketHandlerBuilder::launch_raw) instead.
+    ///
     /// This function does not return, as it will loop forever (unless it panics).
     ///
     /// # Panics
@@ -67,6 +93,310 @@ impl RocketHandlerBuilder {
         unreachable!("lambda! should loop forever (or panic)")
     }
 
+    /// Starts handling Lambda events by polling for events using Lambda's Runtime APIs, receiving
+    /// them as raw JSON with [lambda_runtime::start](lambda_runtime::start).
+    ///
+    /// As well as API Gateway and Application Load Balancer requests, this can handle events from
+    /// API Gateway HTTP APIs using payload format version 2.0, API Gateway WebSocket APIs, VPC Lattice
+    /// and Lambda@Edge, and direct invocations.
+    ///
+    /// Responses are serialised by this crate rather than by `lambda_http`, so they may differ from
+    /// those returned when using [launch()](RocketHandlerBuilder::launch), e.g. responses to Application
+    /// Load Balancer requests include `statusDescription`. Errors are reported in the format used by
+    /// `lambda_runtime`.
+    ///
+    /// This function does not return, as it will loop forever (unless it panics).
+    ///
+    /// # Panics
+    ///
+    /// This panics if the required Lambda runtime environment variables are not set, or if the `Rocket` used to create the builder was misconfigured.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// rocket::ignite().lambda().launch_raw();
+    /// ```
+    pub fn launch_raw(self) -> ! {
+        lambda_runtime::start(self.into_handler(), None);
+        unreachable!("lambda_runtime::start should loop forever (or panic)")
+    }
+
+    /// Runs events from JSON files through the handler and prints the responses, then exits the process.
+    /// This can be used to debug events captured from CloudWatch Logs locally, without deploying the function.
+    ///
+    /// The arguments of the process are read as `[invoke] [--http] [--timeout <SECS>] [FILE]...`. Each file
+    /// may contain several JSON events, and events are read from stdin if no files are given (or a file is `-`).
+    /// Each event is run with a synthetic `Context`, and its response is printed as JSON, or with `--http`, as a
+    /// decoded HTTP response. See [run_cli_with_args](RocketHandlerBuilder::run_cli_with_args) for the exit codes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite().lambda();
+    /// if std::env::args().nth(1).as_ref().map(String::as_str) == Some("invoke") {
+    ///     builder.run_cli();
+    /// } else {
+    ///     builder.launch();
+    /// }
+    /// ```
+    pub fn run_cli(self) -> ! {
+        let args = std::env::args().skip(1);
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+        let exit_code = self.run_cli_with_args(args, &mut stdin.lock(), &mut stdout.lock());
+        std::process::exit(exit_code)
+    }
+
+    /// Runs events through the handler like [run_cli](RocketHandlerBuilder::run_cli), using the given arguments
+    /// (not including the program name), input and output, and returns the exit code. Errors reading the
+    /// arguments or events are written to stderr.
+    ///
+    /// The exit code is `0` if every invocation succeeded, `1` if any invocation returned an error, or `2` if
+    /// the arguments were invalid or an event could not be read.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let event = r#"{
+    ///     "requestContext": { "elb": { "targetGroupArn": "arn:aws:elasticloadbalancing:..." } },
+    ///     "httpMethod": "GET",
+    ///     "path": "/",
+    ///     "headers": { "host": "example.com" },
+    ///     "queryStringParameters": {},
+    ///     "body": "",
+    ///     "isBase64Encoded": false
+    /// }"#;
+    /// let mut output = Vec::new();
+    /// let exit_code = rocket::ignite()
+    ///     .lambda()
+    ///     .run_cli_with_args(vec!["--http"], &mut event.as_bytes(), &mut output);
+    ///
+    /// assert_eq!(exit_code, 0);
+    /// assert!(String::from_utf8(output).unwrap().starts_with("HTTP/1.1 404 Not Found"));
+    /// ```
+    pub fn run_cli_with_args<I, S>(
+        self,
+        args: I,
+        input: &mut dyn Read,
+        output: &mut dyn Write,
+    ) -> i32
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let args = args.into_iter().map(Into::into).collect();
+        cli::run(self.into_handler(), args, input, output)
+    }
+
+    /// Generates an OpenAPI 3 document describing the routes mounted on the `Rocket`, which can be
+    /// imported into API Gateway to create a REST API that forwards each route to the Lambda function.
+    ///
+    /// Each route becomes an operation with an `aws_proxy` integration using `integration_uri`, which has the form
+    /// `arn:aws:apigateway:{region}:lambda:path/2015-03-31/functions/{function_arn}/invocations`.
+    /// Rocket's `<param>` and `<param..>` path segments are translated to `{param}` and `{param+}`. As `<param..>`
+    /// also matches no segments, a route ending with one is also added to the parent path, e.g. `/files/{dir}`.
+    /// API Gateway only allows one path parameter name at each level, so parameters at the same level are renamed
+    /// to the first name in order of path, e.g. `/items/<name>/edit` becomes `/items/{id}/edit` if there is also
+    /// a route for `/items/<id>`.
+    /// Content types whose [response_type](RocketHandlerBuilder::response_type) is `Binary` are listed in
+    /// `x-amazon-apigateway-binary-media-types`, or `*/*` if the default response type is `Binary`.
+    ///
+    /// Routes mounted by [on_cold_start](RocketHandlerBuilder::on_cold_start) or
+    /// [configure_from_stage_variables](RocketHandlerBuilder::configure_from_stage_variables) hooks are not included.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #![feature(proc_macro_hygiene, decl_macro)]
+    ///
+    /// #[macro_use] extern crate rocket;
+    /// use rocket_lamb::{ResponseType, RocketExt};
+    ///
+    /// #[get("/files/<dir>/<path..>")]
+    /// fn file(dir: String, path: std::path::PathBuf) -> Vec<u8> {
+    ///     vec![]
+    /// }
+    ///
+    /// fn main() {
+    ///     let document = rocket::ignite()
+    ///         .mount("/", routes![file])
+    ///         .lambda()
+    ///         .response_type("image/png", ResponseType::Binary)
+    ///         .openapi(
+    ///             "Files",
+    ///             "1.0",
+    ///             "arn:aws:apigateway:eu-west-1:lambda:path/2015-03-31/functions/arn:aws:lambda:eu-west-1:123456789012:function:files/invocations",
+    ///         );
+    ///
+    ///     let operation = &document["paths"]["/files/{dir}/{path+}"]["get"];
+    ///     assert_eq!(operation["x-amazon-apigateway-integration"]["type"], "aws_proxy");
+    ///     assert_eq!(document["x-amazon-apigateway-binary-media-types"][0], "image/png");
+    /// }
+    /// ```
+    pub fn openapi(&self, title: &str, version: &str, integration_uri: &str) -> Value {
+        openapi::document(
+            self.rocket.routes(),
+            &self.config,
+            title,
+            version,
+            integration_uri,
+        )
+    }
+
+    /// Generates a SAM template with an `AWS::Serverless::Function` resource named `function_id`, and events that
+    /// forward requests from `source` to the function.
+    ///
+    /// With [SamRoutes::Mounted], there is an event for each mounted route, with Rocket's `<param>` and
+    /// `<param..>` path segments translated as in [openapi](RocketHandlerBuilder::openapi). With
+    /// [SamRoutes::Greedy], every request is forwarded using a `{proxy+}` path.
+    ///
+    /// - [SamEventSource::RestApi] adds an `AWS::Serverless::Api` named `{function_id}Api`, whose `BinaryMediaTypes`
+    ///   are derived from the configured [response_type](RocketHandlerBuilder::response_type)s.
+    /// - [SamEventSource::HttpApi] adds `HttpApi` events to the implicit HTTP API.
+    /// - [SamEventSource::Alb] adds a target group and listener rules, numbered from its `base_priority`, and a
+    ///   `ListenerArn` template parameter.
+    ///
+    /// The function uses the `provided.al2` runtime with the code in the current directory, which can be changed in
+    /// the returned template.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #![feature(proc_macro_hygiene, decl_macro)]
+    ///
+    /// #[macro_use] extern crate rocket;
+    /// use rocket_lamb::{RocketExt, SamEventSource, SamRoutes};
+    ///
+    /// #[get("/items/<id>")]
+    /// fn item(id: u32) -> String {
+    ///     id.to_string()
+    /// }
+    ///
+    /// fn main() {
+    ///     let template = rocket::ignite()
+    ///         .mount("/", routes![item])
+    ///         .lambda()
+    ///         .sam_template("Items", SamEventSource::HttpApi, SamRoutes::Mounted);
+    ///
+    ///     let event = &template["Resources"]["Items"]["Properties"]["Events"]["GetItemsId"];
+    ///     assert_eq!(event["Properties"]["Path"], "/items/{id}");
+    /// }
+    /// ```
+    pub fn sam_template(
+        &self,
+        function_id: &str,
+        source: SamEventSource,
+        routes: SamRoutes,
+    ) -> Value {
+        sam::template(
+            self.rocket.routes(),
+            &self.config,
+            function_id,
+            source,
+            routes,
+        )
+    }
+
+    /// Replaces every setting included in a [HandlerConfig], e.g. default settings shared between functions
+    /// and deserialized from a configuration file. Settings not included in `HandlerConfig`, such as hooks, are kept.
+    ///
+    /// The settings are treated as configured in code, so they take precedence over settings loaded by
+    /// [load_config](RocketHandlerBuilder::load_config).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{HandlerConfig, PathNormalization, RocketExt};
+    ///
+    /// let config = HandlerConfig {
+    ///     path_normalization: PathNormalization::StripTrailingSlash,
+    ///     ..HandlerConfig::default()
+    /// };
+    /// let builder = rocket::ignite().lambda().config(config.clone());
+    /// assert_eq!(builder.get_config(), config);
+    /// ```
+    pub fn config(mut self, config: HandlerConfig) -> Self {
+        self.config.set_handler_config(config);
+        self.code_settings.set_all();
+        self
+    }
+
+    /// Gets the settings included in a [HandlerConfig].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite().lambda().redact_query_parameter("token");
+    /// assert_eq!(builder.get_config().redacted_query_parameters, vec!["token"]);
+    /// ```
+    pub fn get_config(&self) -> HandlerConfig {
+        self.config.handler_config()
+    }
+
+    /// Loads the settings included in a [HandlerConfig] from `ROCKET_LAMB_*` environment variables and from a
+    /// `[lambda]` table in Rocket's configuration (e.g. `Rocket.toml`), so that they can be changed without
+    /// rebuilding the function.
+    ///
+    /// Settings configured in code, before or after calling this method, take precedence over loaded
+    /// settings, except that loaded response types, extension methods and redacted headers and query
+    /// parameters are added to the configured ones. Environment variables take precedence over Rocket's
+    /// configuration.
+    ///
+    /// | Rocket configuration    | Environment variable                | Values                                          |
+    /// |-------------------------|-------------------------------------|-------------------------------------------------|
+    /// | `default_response_type` | `ROCKET_LAMB_DEFAULT_RESPONSE_TYPE` | `auto`, `text` or `binary`                      |
+    /// | `response_types`        | `ROCKET_LAMB_RESPONSE_TYPES`        | e.g. `{ "image/png" = "binary" }` in Rocket's configuration, or `image/png=binary;text/csv=text` |
+    /// | `base_path_behaviour`   | `ROCKET_LAMB_BASE_PATH_BEHAVIOUR`   | `remount_and_include`, `include` or `exclude`   |
+    ///
+    /// Every other `HandlerConfig` field is read from the key with the same name, or the environment
+    /// variable with the upper-case name, in the format it is serialized in. Environment variables are
+    /// TOML values, as with Rocket's own settings, e.g.
+    /// `ROCKET_LAMB_DEADLINE_WATCHDOG={ margin_ms = 500, status = 504 }` or
+    /// `ROCKET_LAMB_QUERY_RECONSTRUCTION=unordered`.
+    ///
+    /// Returns an error naming the setting if any value is invalid, or a setting is unknown.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket::config::{Config, Environment, Value};
+    /// use rocket_lamb::{BasePathBehaviour, ResponseType, RocketExt};
+    ///
+    /// // Equivalent to a `[production.lambda]` table in Rocket.toml.
+    /// let table: Value = "default_response_type = 'binary'\nbase_path_behaviour = 'exclude'"
+    ///     .parse()
+    ///     .unwrap();
+    /// let config = Config::build(Environment::Production)
+    ///     .extra("lambda", table)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let builder = rocket::custom(config)
+    ///     .lambda()
+    ///     .default_response_type(ResponseType::Text)
+    ///     .load_config()
+    ///     .unwrap();
+    /// assert_eq!(builder.get_default_response_type(), ResponseType::Text);
+    /// assert_eq!(builder.get_base_path_behaviour(), &BasePathBehaviour::Exclude);
+    /// ```
+    pub fn load_config(mut self) -> Result<Self, ConfigError> {
+        config_source::load(
+            &self.rocket,
+            std::env::vars(),
+            &mut self.config,
+            &self.code_settings,
+        )?;
+        Ok(self)
+    }
+
     /// Gets the default `ResponseType`, which is used for any responses that have not had their Content-Type overriden with [response_type](RocketHandlerBuilder::response_type).
     ///
     /// # Example
@@ -97,6 +427,7 @@ impl RocketHandlerBuilder {
     /// ```
     pub fn default_response_type(mut self, response_type: ResponseType) -> Self {
         self.config.default_response_type = response_type;
+        self.code_settings.set("default_response_type");
         self
     }
 
@@ -145,6 +476,20 @@ impl RocketHandlerBuilder {
         self
     }
 
+    /// Gets the configured `BasePathBehaviour`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{BasePathBehaviour, RocketExt};
+    ///
+    /// let builder = rocket::ignite().lambda();
+    /// assert_eq!(builder.get_base_path_behaviour(), &BasePathBehaviour::RemountAndInclude);
+    /// ```
+    pub fn get_base_path_behaviour(&self) -> &BasePathBehaviour {
+        &self.config.base_path_behaviour
+    }
+
     /// Determines whether the API Gateway base path is included in the URL processed by Rocket.
     /// The default is `RemountAndInclude`.
     ///
@@ -175,6 +520,611 @@ impl RocketHandlerBuilder {
     /// ```
     pub fn base_path_behaviour(mut self, setting: BasePathBehaviour) -> Self {
         self.config.base_path_behaviour = setting;
+        self.code_settings.set("base_path_behaviour");
+        self
+    }
+
+    /// Determines how the query string processed by Rocket is rebuilt for requests whose events
+    /// do not include the raw query string, such as API Gateway REST API requests.
+    /// The default is `Ordered`.
+    ///
+    /// HTTP API requests using payload format version 2.0 include the raw query string, which is
+    /// always used verbatim. Application Load Balancer query string parameters are never
+    /// percent-encoded again, as the load balancer passes them through exactly as the client sent them.
+    ///
+    /// The possible values are:
+    /// - `Ordered` - Rebuilds the query string with its keys sorted by name. The values of each key keep
+    /// the order they were sent in, and keys with an empty value are written without `=`, e.g. `?flag`.
+    /// - `Unordered` - Rebuilds the query string in the order of the `HashMap` parsed by `lambda_http`.
+    /// Keys may be reordered between requests, and every key is written with `=`, e.g. `?flag=`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{QueryReconstruction, RocketExt};
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .query_reconstruction(QueryReconstruction::Unordered);
+    /// ```
+    pub fn query_reconstruction(mut self, setting: QueryReconstruction) -> Self {
+        self.config.query_reconstruction = setting;
+        self.code_settings.set("query_reconstruction");
+        self
+    }
+
+    /// Determines how the request path is normalised before it is processed by Rocket.
+    /// The default is `Preserve`.
+    ///
+    /// This applies to API Gateway and Application Load Balancer requests, after the base path
+    /// has been included or excluded according to [base_path_behaviour](RocketHandlerBuilder::base_path_behaviour).
+    ///
+    /// The possible values are:
+    /// - `Preserve` - Passes the path to Rocket unchanged.
+    /// - `StripTrailingSlash` - Removes any trailing slash from the path, e.g. `/path/` becomes `/path`.
+    /// - `CollapseSlashes` - Collapses consecutive slashes into a single slash, e.g. `/a//b` becomes `/a/b`.
+    /// - `Redirect` - Responds with a `308 Permanent Redirect` to the canonical form of the path (with
+    /// duplicate slashes collapsed and any trailing slash removed), without dispatching the request to Rocket.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{PathNormalization, RocketExt};
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .path_normalization(PathNormalization::StripTrailingSlash);
+    /// ```
+    pub fn path_normalization(mut self, setting: PathNormalization) -> Self {
+        self.config.path_normalization = setting;
+        self.code_settings.set("path_normalization");
+        self
+    }
+
+    /// Sets how requests are handled when their HTTP method is not supported by Rocket, unless
+    /// overridden for a specific method with [extension_method](RocketHandlerBuilder::extension_method).
+    /// The default is `Reject`.
+    ///
+    /// Rocket only supports the standard methods (`GET`, `PUT`, `POST`, `DELETE`, `OPTIONS`,
+    /// `HEAD`, `TRACE`, `CONNECT` and `PATCH`), so requests with any other method, such as WebDAV's
+    /// `PROPFIND` or a cache's `PURGE`, cannot be dispatched as they are. This applies to API
+    /// Gateway and Application Load Balancer requests.
+    ///
+    /// The possible values are:
+    /// - `Reject` - Responds with `405 Method Not Allowed`, with an `Allow` header listing the
+    /// methods of the mounted routes whose path matches the request.
+    /// - `MapTo(method)` - Dispatches the request to Rocket with `method` instead.
+    /// - `Fallback(path)` - Dispatches the request to Rocket as a `POST` to `path`.
+    ///
+    /// Requests that are dispatched to Rocket have an `X-Original-Method` header (and for
+    /// `Fallback`, an `X-Original-Uri` header), and the original method is available with the
+    /// [OriginalMethod](crate::OriginalMethod) request guard.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{ExtensionMethodBehaviour, RocketExt};
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .default_extension_method(ExtensionMethodBehaviour::Fallback("/fallback".to_owned()));
+    /// ```
+    pub fn default_extension_method(mut self, behaviour: ExtensionMethodBehaviour) -> Self {
+        self.config.default_extension_method = behaviour;
+        self.code_settings.set("default_extension_method");
+        self
+    }
+
+    /// Sets how requests with the given HTTP method are handled when the method is not supported
+    /// by Rocket. See [default_extension_method](RocketHandlerBuilder::default_extension_method)
+    /// for the possible values.
+    ///
+    /// `method` values are treated case-insensitively.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket::http::Method;
+    /// use rocket_lamb::{ExtensionMethodBehaviour, RocketExt};
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .extension_method("PURGE", ExtensionMethodBehaviour::MapTo(Method::Delete));
+    /// assert_eq!(
+    ///     builder.get_extension_method("purge"),
+    ///     &ExtensionMethodBehaviour::MapTo(Method::Delete)
+    /// );
+    /// assert_eq!(builder.get_extension_method("PROPFIND"), &ExtensionMethodBehaviour::Reject);
+    /// ```
+    pub fn extension_method(mut self, method: &str, behaviour: ExtensionMethodBehaviour) -> Self {
+        self.config
+            .extension_methods
+            .insert(method.to_uppercase(), behaviour);
+        self
+    }
+
+    /// Gets how requests with the given HTTP method are handled when the method is not supported
+    /// by Rocket.
+    ///
+    /// `method` values are treated case-insensitively.
+    pub fn get_extension_method(&self, method: &str) -> &ExtensionMethodBehaviour {
+        self.config
+            .extension_methods
+            .get(&method.to_uppercase())
+            .unwrap_or(&self.config.default_extension_method)
+    }
+
+    /// Sets the route that handles events which did not come from API Gateway, an Application
+    /// Load Balancer or a WebSocket API, e.g. Step Functions tasks or direct `Invoke` calls.
+    ///
+    /// The event is dispatched to Rocket as a `POST` request to `route` with a Content-Type of
+    /// `application/json` and the event as the request body. The response body must be JSON (or
+    /// empty, which is returned as `null`), and is returned as the result of the invocation. If
+    /// the route responds with a non-2xx status, the invocation fails with an error containing the
+    /// status and response body.
+    ///
+    /// By default there is no direct invocation route, and such events fail to process. Direct
+    /// invocations are only supported when handling raw JSON events, e.g. when using
+    /// [launch_raw()](RocketHandlerBuilder::launch_raw).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .direct_invocation_route("/invoke");
+    /// assert_eq!(builder.get_direct_invocation_route(), Some("/invoke"));
+    /// ```
+    pub fn direct_invocation_route(mut self, route: &str) -> Self {
+        self.config.direct_invocation_route = Some(route.to_owned());
+        self.code_settings.set("direct_invocation_route");
+        self
+    }
+
+    /// Gets the route that handles direct invocations, if one has been set with
+    /// [direct_invocation_route](RocketHandlerBuilder::direct_invocation_route).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite().lambda();
+    /// assert_eq!(builder.get_direct_invocation_route(), None);
+    /// ```
+    pub fn get_direct_invocation_route(&self) -> Option<&str> {
+        self.config
+            .direct_invocation_route
+            .as_ref()
+            .map(String::as_str)
+    }
+
+    /// Sets the status code that a route can respond with to let a Lambda@Edge request continue
+    /// to the origin, rather than returning a generated response to the viewer.
+    ///
+    /// CloudFront viewer request and origin request events are converted into Rocket requests
+    /// using the CloudFront request's method, uri, querystring, headers and (if included) body.
+    /// Normally the Rocket response is converted into a CloudFront generated response. Generated
+    /// responses must not exceed Lambda@Edge's body size limits (40 KB for viewer requests and
+    /// 1 MB for origin requests, after base-64 encoding of binary bodies), otherwise the invocation fails.
+    ///
+    /// When a route responds with the given `status`, the original CloudFront request is returned
+    /// instead so that it continues to the origin. Any headers on the route's response are added to
+    /// the request, replacing any existing headers with the same name, and the response body is ignored.
+    ///
+    /// By default there is no such status, so every request gets a generated response. Lambda@Edge
+    /// events are only supported when handling raw JSON events, e.g. when using
+    /// [launch_raw()](RocketHandlerBuilder::launch_raw).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .edge_continue_status(299);
+    /// assert_eq!(builder.get_edge_continue_status(), Some(299));
+    /// ```
+    pub fn edge_continue_status(mut self, status: u16) -> Self {
+        self.config.edge_continue_status = Some(status);
+        self.code_settings.set("edge_continue_status");
+        self
+    }
+
+    /// Gets the status code that lets a Lambda@Edge request continue to the origin, if one has
+    /// been set with [edge_continue_status](RocketHandlerBuilder::edge_continue_status).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite().lambda();
+    /// assert_eq!(builder.get_edge_continue_status(), None);
+    /// ```
+    pub fn get_edge_continue_status(&self) -> Option<u16> {
+        self.config.edge_continue_status
+    }
+
+    /// Sets a function that configures the `Rocket` using the stage variables of the API Gateway
+    /// stage, e.g. to add managed state. This allows one function to be configured differently
+    /// for each stage without environment variables.
+    ///
+    /// The function is called once, when the first event is received and before the `Rocket`
+    /// starts handling requests. If the first event is not an API Gateway request, it receives
+    /// no stage variables. Stage variables are also available to routes with the
+    /// [StageVariables](crate::StageVariables) request guard.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// struct TableName(String);
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .configure_from_stage_variables(|rocket, variables| {
+    ///         let table = variables.get("table").unwrap_or("dev").to_owned();
+    ///         rocket.manage(TableName(table))
+    ///     });
+    /// ```
+    pub fn configure_from_stage_variables<F>(mut self, configure: F) -> Self
+    where
+        F: FnOnce(Rocket, &StageVariables) -> Rocket + Send + 'static,
+    {
+        self.config.stage_variables_hook = Some(Box::new(configure));
+        self
+    }
+
+    /// Enables a watchdog that responds to a request when the invocation is about to reach its
+    /// deadline, instead of letting Lambda stop the invocation while the request is in flight.
+    ///
+    /// When the watchdog is enabled, each event is processed on another thread. If processing has
+    /// not completed `margin` before the deadline, a response with the given `status` (typically
+    /// `503` or `504`) and an empty body is returned, and the route expected to handle the
+    /// overrunning request is logged to stderr. Lambda@Edge requests and direct invocations fail
+    /// with an error instead. The next invocation waits for the overrunning request to complete
+    /// before it is processed, but if the request is still running when the next invocation's own
+    /// watchdog would respond, the next invocation also responds with `status`.
+    ///
+    /// If processing an event panics, the invocation fails with an error, and later invocations
+    /// are processed as normal.
+    ///
+    /// The time remaining until the deadline is available to routes with the
+    /// [Deadline](crate::Deadline) request guard.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    /// use std::time::Duration;
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .deadline_watchdog(Duration::from_millis(500), 504);
+    /// ```
+    pub fn deadline_watchdog(mut self, margin: Duration, status: u16) -> Self {
+        self.config.deadline_watchdog = Some((margin, status));
+        self.code_settings.set("deadline_watchdog");
+        self
+    }
+
+    /// Adds a function that is called with the `Rocket` when the first event is received, before
+    /// the `Rocket` starts handling requests, e.g. to add managed state or fairings that depend
+    /// on the Lambda `Context`.
+    ///
+    /// Functions are called in the order they were added.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// struct FunctionName(String);
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .on_cold_start(|rocket, ctx| rocket.manage(FunctionName(ctx.function_name.clone())));
+    /// ```
+    pub fn on_cold_start<F>(mut self, hook: F) -> Self
+    where
+        F: FnOnce(Rocket, &Context) -> Rocket + Send + 'static,
+    {
+        self.config.cold_start_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Adds a function that is called with each API Gateway or Application Load Balancer request
+    /// before it is processed by Rocket. The function may modify the request, or return a
+    /// response to send it instead of processing the request with Rocket.
+    ///
+    /// Functions are called in the order they were added, until one returns a response.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lambda_http::{Body, Response};
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .before_request(|req, _ctx| {
+    ///         if req.headers().contains_key("x-maintenance") {
+    ///             Some(Response::builder().status(503).body(Body::Empty).unwrap())
+    ///         } else {
+    ///             req.headers_mut().insert("x-lambda", "true".parse().unwrap());
+    ///             None
+    ///         }
+    ///     });
+    /// ```
+    pub fn before_request<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut Request, &Context) -> Option<Response<Body>> + Send + Sync + 'static,
+    {
+        self.config.before_request_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Adds a function that is called with each response from Rocket, along with the Lambda
+    /// response that it was converted into. The function may modify the Lambda response.
+    ///
+    /// Functions are called in the order they were added. They are not called for responses
+    /// that were not produced by Rocket, e.g. those returned from
+    /// [before_request](RocketHandlerBuilder::before_request) functions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .after_response(|rocket_res, res, ctx| {
+    ///         let request_id = ctx.aws_request_id.parse().unwrap();
+    ///         res.headers_mut().insert("x-request-id", request_id);
+    ///         assert_eq!(rocket_res.status().code, res.status().as_u16());
+    ///     });
+    /// ```
+    pub fn after_response<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&rocket::Response, &mut Response<Body>, &Context) + Send + Sync + 'static,
+    {
+        self.config.after_response_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Enables access logging, which records one entry per API Gateway or Application Load
+    /// Balancer request to the given sink. Use [StdoutAccessLog](crate::StdoutAccessLog) to write
+    /// entries to CloudWatch Logs as JSON or in the Common Log Format.
+    ///
+    /// The values of sensitive headers (by default `Authorization`, `Cookie`,
+    /// `Proxy-Authorization` and `X-Api-Key`) are redacted from entries, along with any headers
+    /// and query parameters added with [redact_header](RocketHandlerBuilder::redact_header) and
+    /// [redact_query_parameter](RocketHandlerBuilder::redact_query_parameter).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{AccessLogFormat, RocketExt, StdoutAccessLog};
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .access_log(StdoutAccessLog(AccessLogFormat::Common));
+    /// ```
+    pub fn access_log<S: AccessLogSink + 'static>(mut self, sink: S) -> Self {
+        self.config.access_log = Some(Box::new(sink));
+        self
+    }
+
+    /// Redacts the value of the given header from access log entries and recorded invocations.
+    ///
+    /// `name` values are treated case-insensitively.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{AccessLogFormat, RocketExt, StdoutAccessLog};
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .access_log(StdoutAccessLog(AccessLogFormat::Json))
+    ///     .redact_header("X-Session-Token");
+    /// ```
+    pub fn redact_header(mut self, name: &str) -> Self {
+        self.config.redacted_headers.push(name.to_owned());
+        self
+    }
+
+    /// Redacts the value of the given query parameter from access log entries and recorded invocations.
+    ///
+    /// `name` is compared with the query parameter names exactly as they appear in the query string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{AccessLogFormat, RocketExt, StdoutAccessLog};
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .access_log(StdoutAccessLog(AccessLogFormat::Json))
+    ///     .redact_query_parameter("access_token");
+    /// ```
+    pub fn redact_query_parameter(mut self, name: &str) -> Self {
+        self.config.redacted_query_parameters.push(name.to_owned());
+        self
+    }
+
+    /// Enables recording, which passes each raw JSON event received by the handler (e.g. when using
+    /// [launch_raw](RocketHandlerBuilder::launch_raw)), along with the response or error it produced, to the given sink.
+    /// Use [StdoutRecordingSink](crate::StdoutRecordingSink) to write them to CloudWatch Logs, or
+    /// [DirectoryRecordingSink](crate::DirectoryRecordingSink) to write them to local files.
+    ///
+    /// Recorded invocations can be replayed through a handler with
+    /// [RecordedInvocation::replay](crate::RecordedInvocation::replay) to reproduce a problem, or to check
+    /// that responses have not changed.
+    ///
+    /// Only raw JSON events are recorded, so requests received through
+    /// [lambda_http::lambda!](lambda_http::lambda) (including when using [launch()](RocketHandlerBuilder::launch))
+    /// are not, as the event they were parsed from is not available.
+    ///
+    /// The values of sensitive headers and query parameters are redacted from recorded events and responses,
+    /// in the same way as for [access_log](RocketHandlerBuilder::access_log). The values of the API Gateway
+    /// `requestContext.authorizer` (e.g. JWT or Cognito claims) and `requestContext.identity` fields are always
+    /// redacted. Request and response bodies are also redacted, unless
+    /// [record_bodies](RocketHandlerBuilder::record_bodies) is used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{RocketExt, StdoutRecordingSink};
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .record_events(StdoutRecordingSink)
+    ///     .redact_header("X-Session-Token");
+    /// ```
+    pub fn record_events<S: RecordingSink + 'static>(mut self, sink: S) -> Self {
+        self.config.recording = Some(Arc::new(sink));
+        self
+    }
+
+    /// Includes request and response bodies in invocations recorded with
+    /// [record_events](RocketHandlerBuilder::record_events), so that replayed requests have the same body.
+    /// Bodies may contain personal data or credentials, so by default they are redacted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{DirectoryRecordingSink, RocketExt};
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .record_events(DirectoryRecordingSink::new("recordings"))
+    ///     .record_bodies();
+    /// ```
+    pub fn record_bodies(mut self) -> Self {
+        self.config.record_bodies = true;
+        self.code_settings.set("record_bodies");
+        self
+    }
+
+    /// Enables CloudWatch metrics, which are written to stdout in the Embedded Metric Format so
+    /// that no metrics agent is needed.
+    ///
+    /// For each API Gateway or Application Load Balancer request, the following metrics are
+    /// recorded in `namespace`, dimensioned by the API Gateway `Stage` and the Rocket `Route` that
+    /// handled the request (identified by its method and URI, e.g. `GET /items/<id>`, so that the
+    /// number of metrics does not grow with the number of distinct paths):
+    /// - `Latency` - the time taken to process the request, in milliseconds.
+    /// - `2xx`, `3xx`, `4xx` and `5xx` - 1 for the class of the response status, and 0 for the others.
+    /// - `ResponseBytes` - the size of the response body.
+    /// - `Base64Responses` - 1 if the response body was base-64 encoded, otherwise 0.
+    /// - `ColdStart` - 1 for the first request processed by the handler, otherwise 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite().lambda().emf_metrics("MyService");
+    /// ```
+    pub fn emf_metrics(self, namespace: &str) -> Self {
+        self.emf_metrics_writer(namespace, io::stdout())
+    }
+
+    /// Enables CloudWatch metrics in the Embedded Metric Format, as with
+    /// [emf_metrics](RocketHandlerBuilder::emf_metrics), but writes them to `writer` instead of stdout.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .emf_metrics_writer("MyService", std::io::stderr());
+    /// ```
+    pub fn emf_metrics_writer<W>(mut self, namespace: &str, writer: W) -> Self
+    where
+        W: Write + Send + 'static,
+    {
+        self.config.metrics = Some(EmfMetrics::new(namespace, Box::new(writer)));
+        self.code_settings.set("emf_metrics_namespace");
+        self
+    }
+
+    /// Enables AWS X-Ray tracing, which sends a segment for each API Gateway or Application Load
+    /// Balancer request to the X-Ray daemon at the address in `AWS_XRAY_DAEMON_ADDRESS` (as set
+    /// by Lambda when active tracing is enabled).
+    ///
+    /// Each segment is a subsegment of the Lambda function's segment, and records the Rocket
+    /// route that handled the request, the response status, and whether the response was an
+    /// error (`4xx`), a throttle (`429`) or a fault (`5xx`). Segments are not sent for traces
+    /// that are not sampled.
+    ///
+    /// The trace context is available to routes with the [TraceContext](crate::TraceContext)
+    /// request guard whether or not tracing is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite().lambda().xray_tracing();
+    /// ```
+    pub fn xray_tracing(mut self) -> Self {
+        self.config.xray = Some(XRayEmitter::new(None));
+        self.code_settings.set("xray_tracing");
+        self
+    }
+
+    /// Enables AWS X-Ray tracing as with [xray_tracing](RocketHandlerBuilder::xray_tracing), but
+    /// sends segments to the daemon at the given UDP address.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::RocketExt;
+    ///
+    /// let builder = rocket::ignite()
+    ///     .lambda()
+    ///     .xray_daemon_address("127.0.0.1:2000");
+    /// ```
+    pub fn xray_daemon_address(mut self, address: &str) -> Self {
+        self.config.xray = Some(XRayEmitter::new(Some(address)));
+        self.code_settings.set("xray_tracing");
+        self.code_settings.set("xray_daemon_address");
+        self
+    }
+
+    /// Enables OpenTelemetry tracing, which creates a server span for each API Gateway or
+    /// Application Load Balancer request and sends it to `exporter`. Use
+    /// [OtlpHttpExporter](crate::OtlpHttpExporter) to send spans to an OpenTelemetry collector.
+    ///
+    /// If the request has a W3C `traceparent` header, or failing that, an X-Ray trace context,
+    /// the span continues that trace. Spans are named after the Rocket route that handled the
+    /// request (e.g. `GET /items/<id>`), and have the `http.request.method`, `http.route`,
+    /// `http.response.status_code`, `url.full`, `faas.trigger`, `faas.invocation_id` and
+    /// `faas.coldstart` attributes.
+    ///
+    /// The span's context is available to routes with the [SpanContext](crate::SpanContext)
+    /// request guard.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{InMemorySpanExporter, RocketExt};
+    /// use std::sync::Arc;
+    ///
+    /// let exporter = Arc::new(InMemorySpanExporter::default());
+    /// let builder = rocket::ignite().lambda().opentelemetry(exporter.clone());
+    /// ```
+    pub fn opentelemetry<E: SpanExporter + 'static>(mut self, exporter: E) -> Self {
+        self.config.span_exporter = Some(Box::new(exporter));
         self
     }
 }
@@ -0,0 +1,158 @@
+use crate::handler::{local_context, RocketHandler};
+use crate::response::{body_from_json, headers_from_json};
+use http::StatusCode;
+use lambda_runtime::error::LambdaErrorExt;
+use lambda_runtime::Handler;
+use serde_json::{json, Deserializer, Value};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+const USAGE: &str = "\
+Runs Lambda events through the Rocket handler and prints the responses.
+
+USAGE:
+    <program> [invoke] [OPTIONS] [FILE]...
+
+Each FILE contains one or more JSON events. Events are read from stdin if no files are given, or a FILE is `-`.
+
+OPTIONS:
+    --http              Prints each response as a decoded HTTP response, instead of as JSON
+    --timeout <SECS>    Sets the time until the deadline of each invocation [default: 30]
+    -h, --help          Prints this message";
+
+/// The exit code when every invocation succeeded.
+const EXIT_SUCCESS: i32 = 0;
+/// The exit code when any invocation returned an error.
+const EXIT_FAILURE: i32 = 1;
+/// The exit code when the arguments were invalid, or an event could not be read.
+const EXIT_USAGE: i32 = 2;
+
+struct Options {
+    files: Vec<String>,
+    http: bool,
+    timeout: Duration,
+}
+
+fn parse_args(args: Vec<String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        files: Vec::new(),
+        http: false,
+        timeout: Duration::from_secs(30),
+    };
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(String::as_str) == Some("invoke") {
+        args.next();
+    }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--http" => options.http = true,
+            "--timeout" => {
+                let secs = args
+                    .next()
+                    .ok_or_else(|| "--timeout requires a value".to_owned())?;
+                let secs = secs
+                    .parse()
+                    .map_err(|_| format!("invalid timeout '{}'", secs))?;
+                options.timeout = Duration::from_secs(secs);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'", arg)),
+            _ => options.files.push(arg),
+        }
+    }
+    if options.files.is_empty() {
+        options.files.push("-".to_owned());
+    }
+    Ok(Some(options))
+}
+
+/// Reads every event from a file, or from `input` if the file name is `-`.
+fn read_events(file: &str, input: &mut dyn Read) -> Result<Vec<Value>, String> {
+    let mut contents = String::new();
+    let read = if file == "-" {
+        input.read_to_string(&mut contents)
+    } else {
+        File::open(file).and_then(|mut f| f.read_to_string(&mut contents))
+    };
+    read.map_err(|e| format!("could not read '{}': {}", file, e))?;
+
+    Deserializer::from_str(&contents)
+        .into_iter::<Value>()
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("could not parse '{}': {}", file, e))
+}
+
+/// Writes a response as the status line, headers and body of an HTTP response.
+fn write_http(output: &mut dyn Write, res: &Value) -> io::Result<()> {
+    let status = res["statusCode"].as_u64().unwrap_or_default() as u16;
+    let reason = StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or_default();
+    writeln!(output, "HTTP/1.1 {} {}", status, reason)?;
+    for (name, value) in headers_from_json(res) {
+        writeln!(output, "{}: {}", name, value)?;
+    }
+    writeln!(output)?;
+    match body_from_json(res) {
+        Ok(body) => match String::from_utf8(body) {
+            Ok(text) => writeln!(output, "{}", text),
+            Err(e) => writeln!(output, "<{} bytes of binary data>", e.as_bytes().len()),
+        },
+        Err(e) => writeln!(output, "<invalid base64 body: {}>", e),
+    }
+}
+
+pub(crate) fn run(
+    mut handler: RocketHandler,
+    args: Vec<String>,
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+) -> i32 {
+    let options = match parse_args(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            let _ = writeln!(output, "{}", USAGE);
+            return EXIT_SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    let mut events = Vec::new();
+    for file in &options.files {
+        match read_events(file, input) {
+            Ok(file_events) => events.extend(file_events),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return EXIT_USAGE;
+            }
+        }
+    }
+
+    let mut exit_code = EXIT_SUCCESS;
+    for (i, event) in events.into_iter().enumerate() {
+        let ctx = local_context(format!("cli-request-{}", i + 1), options.timeout);
+        let written = match handler.run(event, ctx) {
+            // Responses to events that are not HTTP requests are always written as JSON.
+            Ok(res) if options.http && res["statusCode"].is_u64() => write_http(output, &res),
+            Ok(res) => writeln!(output, "{:#}", res),
+            Err(e) => {
+                exit_code = EXIT_FAILURE;
+                let error = json!({
+                    "errorType": e.error_type(),
+                    "errorMessage": e.to_string(),
+                });
+                writeln!(output, "{:#}", error)
+            }
+        };
+        if let Err(e) = written.and_then(|_| output.flush()) {
+            eprintln!("error: could not write response: {}", e);
+            return EXIT_FAILURE;
+        }
+    }
+    exit_code
+}
@@ -1,13 +1,54 @@
-use std::collections::HashMap;
+use crate::access_log::{AccessLogSink, DEFAULT_REDACTED_HEADERS};
+use crate::metrics::EmfMetrics;
+use crate::otel::SpanExporter;
+use crate::params::StageVariables;
+use crate::recording::RecordingSink;
+use crate::xray::XRayEmitter;
+use lambda_http::{Body, Request, Response};
+use lambda_runtime::Context;
+use rocket::http::Method;
+use rocket::Rocket;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub(crate) type StageVariablesHook = Box<dyn FnOnce(Rocket, &StageVariables) -> Rocket + Send>;
+pub(crate) type ColdStartHook = Box<dyn FnOnce(Rocket, &Context) -> Rocket + Send>;
+pub(crate) type BeforeRequestHook =
+    Box<dyn Fn(&mut Request, &Context) -> Option<Response<Body>> + Send + Sync>;
+pub(crate) type AfterResponseHook =
+    Box<dyn Fn(&rocket::Response, &mut Response<Body>, &Context) + Send + Sync>;
 
 pub(crate) struct Config {
     pub(crate) default_response_type: ResponseType,
     pub(crate) response_types: HashMap<String, ResponseType>,
     pub(crate) base_path_behaviour: BasePathBehaviour,
+    pub(crate) query_reconstruction: QueryReconstruction,
+    pub(crate) path_normalization: PathNormalization,
+    pub(crate) default_extension_method: ExtensionMethodBehaviour,
+    pub(crate) extension_methods: HashMap<String, ExtensionMethodBehaviour>,
+    pub(crate) direct_invocation_route: Option<String>,
+    pub(crate) edge_continue_status: Option<u16>,
+    pub(crate) stage_variables_hook: Option<StageVariablesHook>,
+    pub(crate) deadline_watchdog: Option<(Duration, u16)>,
+    pub(crate) cold_start_hooks: Vec<ColdStartHook>,
+    pub(crate) before_request_hooks: Vec<BeforeRequestHook>,
+    pub(crate) after_response_hooks: Vec<AfterResponseHook>,
+    pub(crate) access_log: Option<Box<dyn AccessLogSink>>,
+    pub(crate) recording: Option<Arc<dyn RecordingSink>>,
+    pub(crate) record_bodies: bool,
+    pub(crate) redacted_headers: Vec<String>,
+    pub(crate) redacted_query_parameters: Vec<String>,
+    pub(crate) metrics: Option<EmfMetrics>,
+    pub(crate) xray: Option<XRayEmitter>,
+    pub(crate) span_exporter: Option<Box<dyn SpanExporter>>,
 }
 
 /// Determines how to encode response content. The default is `Auto`.
-#[derive(PartialEq, Copy, Clone, Debug)]
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ResponseType {
     /// Attempts to encode response content as a UTF-8 string. If it is not valid UTF-8, it is instead encoded as base-64.
     Auto,
@@ -19,7 +60,8 @@ pub enum ResponseType {
 
 /// Determines whether the API Gateway base path is included in the URL processed by Rocket.
 /// The default is `RemountAndInclude`.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum BasePathBehaviour {
     /// Includes the base bath in the URL. The first request received will be used to determine
     /// the base path, and all mounted routes will be cloned and re-mounted at the base path.
@@ -33,12 +75,301 @@ pub enum BasePathBehaviour {
     Exclude,
 }
 
+/// Determines how the query string processed by Rocket is rebuilt for requests whose events do not
+/// include the raw query string, such as API Gateway REST API requests. The default is `Ordered`.
+///
+/// This has no effect for HTTP API requests using payload format version 2.0, as the raw query
+/// string is always used verbatim.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryReconstruction {
+    /// Rebuilds the query string with its keys sorted by name, so that the same parameters always
+    /// produce the same query string. The values of each key keep the order they were sent in, and
+    /// keys with an empty value are written without `=`, e.g. `?flag`.
+    Ordered,
+    /// Rebuilds the query string in the order of the `HashMap` parsed by `lambda_http`. Keys may
+    /// be reordered between requests, and every key is written with `=`, e.g. `?flag=`.
+    Unordered,
+}
+
+/// Determines how the request path is normalised before it is processed by Rocket.
+/// The default is `Preserve`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathNormalization {
+    /// Passes the path to Rocket unchanged.
+    Preserve,
+    /// Removes any trailing slash from the path, e.g. `/path/` becomes `/path`. The root path `/` is unchanged.
+    StripTrailingSlash,
+    /// Collapses consecutive slashes in the path into a single slash, e.g. `/a//b` becomes `/a/b`.
+    CollapseSlashes,
+    /// Responds with a `308 Permanent Redirect` to the canonical form of the path (with duplicate
+    /// slashes collapsed and any trailing slash removed), without dispatching the request to Rocket.
+    /// Requests whose path is already canonical are passed to Rocket unchanged.
+    Redirect,
+}
+
+impl PathNormalization {
+    /// Applies the normalization to `path`. For `Redirect`, this returns the canonical path.
+    pub(crate) fn apply(&self, path: &str) -> String {
+        match self {
+            PathNormalization::Preserve => path.to_owned(),
+            PathNormalization::StripTrailingSlash => strip_trailing_slash(path).to_owned(),
+            PathNormalization::CollapseSlashes => collapse_slashes(path),
+            PathNormalization::Redirect => strip_trailing_slash(&collapse_slashes(path)).to_owned(),
+        }
+    }
+}
+
+fn strip_trailing_slash(path: &str) -> &str {
+    match path.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    }
+}
+
+fn collapse_slashes(path: &str) -> String {
+    let mut collapsed = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c != '/' || !collapsed.ends_with('/') {
+            collapsed.push(c);
+        }
+    }
+    collapsed
+}
+
+/// Determines how requests are handled when their HTTP method is not supported by Rocket, e.g.
+/// `PROPFIND` or `PURGE`. The default is `Reject`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtensionMethodBehaviour {
+    /// Responds with `405 Method Not Allowed`, without dispatching the request to Rocket. The
+    /// `Allow` header lists the methods of the mounted routes whose path matches the request.
+    Reject,
+    /// Dispatches the request to Rocket with the given method instead. The original method is set
+    /// in the `X-Original-Method` header, and is available with the
+    /// [OriginalMethod](crate::OriginalMethod) request guard.
+    MapTo(#[serde(with = "method_name")] Method),
+    /// Dispatches the request to Rocket as a `POST` to the given path, e.g. a fallback route mounted
+    /// at `/fallback`. The original method and URI are set in the `X-Original-Method` and
+    /// `X-Original-Uri` headers, and the original method is available with the
+    /// [OriginalMethod](crate::OriginalMethod) request guard.
+    Fallback(String),
+}
+
+/// Serializes a `Method` as its name, e.g. `"POST"`.
+mod method_name {
+    use rocket::http::Method;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        method: &Method,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(method.as_str())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Method, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| D::Error::custom(format!("unknown HTTP method \"{}\"", name)))
+    }
+}
+
+/// The settings of a [RocketHandler](crate::RocketHandler) that can be serialized, e.g. to share default settings
+/// between functions in a configuration file, or to log the effective settings at cold start.
+///
+/// Hooks, access logs, recording and OpenTelemetry exporters can only be configured with
+/// [RocketHandlerBuilder](crate::RocketHandlerBuilder) methods, and are not included.
+///
+/// Missing fields are deserialized with their default values, and unknown fields are rejected.
+///
+/// # Example
+///
+/// ```rust
+/// use rocket_lamb::{HandlerConfig, ResponseType, RocketExt};
+///
+/// let config: HandlerConfig = serde_json::from_str(r#"{
+///     "default_response_type": "text",
+///     "response_types": { "image/png": "binary" },
+///     "extension_methods": { "PURGE": { "map_to": "DELETE" } },
+///     "deadline_watchdog": { "margin_ms": 500, "status": 504 }
+/// }"#).unwrap();
+///
+/// let builder = rocket::ignite().lambda().config(config);
+/// assert_eq!(builder.get_default_response_type(), ResponseType::Text);
+/// assert_eq!(builder.get_response_type("image/png"), ResponseType::Binary);
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HandlerConfig {
+    /// See [default_response_type](crate::RocketHandlerBuilder::default_response_type).
+    pub default_response_type: ResponseType,
+    /// The `ResponseType` for each Content-Type, see [response_type](crate::RocketHandlerBuilder::response_type).
+    pub response_types: BTreeMap<String, ResponseType>,
+    /// See [base_path_behaviour](crate::RocketHandlerBuilder::base_path_behaviour).
+    pub base_path_behaviour: BasePathBehaviour,
+    /// See [query_reconstruction](crate::RocketHandlerBuilder::query_reconstruction).
+    pub query_reconstruction: QueryReconstruction,
+    /// See [path_normalization](crate::RocketHandlerBuilder::path_normalization).
+    pub path_normalization: PathNormalization,
+    /// See [default_extension_method](crate::RocketHandlerBuilder::default_extension_method).
+    pub default_extension_method: ExtensionMethodBehaviour,
+    /// The behaviour for each HTTP method, see [extension_method](crate::RocketHandlerBuilder::extension_method).
+    pub extension_methods: BTreeMap<String, ExtensionMethodBehaviour>,
+    /// See [direct_invocation_route](crate::RocketHandlerBuilder::direct_invocation_route).
+    pub direct_invocation_route: Option<String>,
+    /// See [edge_continue_status](crate::RocketHandlerBuilder::edge_continue_status).
+    pub edge_continue_status: Option<u16>,
+    /// See [deadline_watchdog](crate::RocketHandlerBuilder::deadline_watchdog).
+    pub deadline_watchdog: Option<WatchdogConfig>,
+    /// See [record_bodies](crate::RocketHandlerBuilder::record_bodies).
+    pub record_bodies: bool,
+    /// The headers whose values are redacted, including the default ones. See
+    /// [redact_header](crate::RocketHandlerBuilder::redact_header).
+    pub redacted_headers: Vec<String>,
+    /// See [redact_query_parameter](crate::RocketHandlerBuilder::redact_query_parameter).
+    pub redacted_query_parameters: Vec<String>,
+    /// The namespace of metrics written to stdout, see [emf_metrics](crate::RocketHandlerBuilder::emf_metrics).
+    pub emf_metrics_namespace: Option<String>,
+    /// See [xray_tracing](crate::RocketHandlerBuilder::xray_tracing).
+    pub xray_tracing: bool,
+    /// The address segments are sent to when `xray_tracing` is enabled, see
+    /// [xray_daemon_address](crate::RocketHandlerBuilder::xray_daemon_address). If this is `None`, the address in
+    /// `AWS_XRAY_DAEMON_ADDRESS` is used.
+    pub xray_daemon_address: Option<String>,
+}
+
+/// The settings of the [deadline_watchdog](crate::RocketHandlerBuilder::deadline_watchdog) in a [HandlerConfig].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchdogConfig {
+    /// The time before the deadline at which the watchdog responds, in milliseconds.
+    pub margin_ms: u64,
+    /// The status code of the watchdog's response.
+    pub status: u16,
+}
+
+impl Default for HandlerConfig {
+    fn default() -> HandlerConfig {
+        Config::default().handler_config()
+    }
+}
+
+impl Config {
+    /// Gets the settings that can be serialized.
+    pub(crate) fn handler_config(&self) -> HandlerConfig {
+        HandlerConfig {
+            default_response_type: self.default_response_type,
+            response_types: self
+                .response_types
+                .iter()
+                .map(|(k, v)| (k.clone(), *v))
+                .collect(),
+            base_path_behaviour: self.base_path_behaviour.clone(),
+            query_reconstruction: self.query_reconstruction.clone(),
+            path_normalization: self.path_normalization.clone(),
+            default_extension_method: self.default_extension_method.clone(),
+            extension_methods: self
+                .extension_methods
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            direct_invocation_route: self.direct_invocation_route.clone(),
+            edge_continue_status: self.edge_continue_status,
+            deadline_watchdog: self
+                .deadline_watchdog
+                .map(|(margin, status)| WatchdogConfig {
+                    margin_ms: margin.as_millis() as u64,
+                    status,
+                }),
+            record_bodies: self.record_bodies,
+            redacted_headers: self.redacted_headers.clone(),
+            redacted_query_parameters: self.redacted_query_parameters.clone(),
+            emf_metrics_namespace: self.metrics.as_ref().map(|m| m.namespace().to_owned()),
+            xray_tracing: self.xray.is_some(),
+            xray_daemon_address: self
+                .xray
+                .as_ref()
+                .and_then(|x| x.configured_address().map(str::to_owned)),
+        }
+    }
+
+    /// Replaces the settings that can be serialized. If metrics were already enabled, they are still written
+    /// to the same writer, even if the namespace changes.
+    pub(crate) fn set_handler_config(&mut self, config: HandlerConfig) {
+        self.default_response_type = config.default_response_type;
+        self.response_types = config
+            .response_types
+            .into_iter()
+            .map(|(k, v)| (k.to_lowercase(), v))
+            .collect();
+        self.base_path_behaviour = config.base_path_behaviour;
+        self.query_reconstruction = config.query_reconstruction;
+        self.path_normalization = config.path_normalization;
+        self.default_extension_method = config.default_extension_method;
+        self.extension_methods = config
+            .extension_methods
+            .into_iter()
+            .map(|(k, v)| (k.to_uppercase(), v))
+            .collect();
+        self.direct_invocation_route = config.direct_invocation_route;
+        self.edge_continue_status = config.edge_continue_status;
+        self.deadline_watchdog = config
+            .deadline_watchdog
+            .map(|w| (Duration::from_millis(w.margin_ms), w.status));
+        self.record_bodies = config.record_bodies;
+        self.redacted_headers = config.redacted_headers;
+        self.redacted_query_parameters = config.redacted_query_parameters;
+        self.metrics = match (self.metrics.take(), config.emf_metrics_namespace) {
+            (Some(mut metrics), Some(namespace)) => {
+                metrics.set_namespace(&namespace);
+                Some(metrics)
+            }
+            (None, Some(namespace)) => Some(EmfMetrics::new(&namespace, Box::new(io::stdout()))),
+            (_, None) => None,
+        };
+        self.xray = if config.xray_tracing {
+            Some(XRayEmitter::new(
+                config.xray_daemon_address.as_ref().map(String::as_str),
+            ))
+        } else {
+            None
+        };
+    }
+}
+
 impl Default for Config {
     fn default() -> Config {
         Config {
             default_response_type: ResponseType::Auto,
             response_types: HashMap::new(),
             base_path_behaviour: BasePathBehaviour::RemountAndInclude,
+            query_reconstruction: QueryReconstruction::Ordered,
+            path_normalization: PathNormalization::Preserve,
+            default_extension_method: ExtensionMethodBehaviour::Reject,
+            extension_methods: HashMap::new(),
+            direct_invocation_route: None,
+            edge_continue_status: None,
+            stage_variables_hook: None,
+            deadline_watchdog: None,
+            cold_start_hooks: Vec::new(),
+            before_request_hooks: Vec::new(),
+            after_response_hooks: Vec::new(),
+            access_log: None,
+            recording: None,
+            record_bodies: false,
+            redacted_headers: DEFAULT_REDACTED_HEADERS
+                .iter()
+                .map(|h| (*h).to_owned())
+                .collect(),
+            redacted_query_parameters: Vec::new(),
+            metrics: None,
+            xray: None,
+            span_exporter: None,
         }
     }
 }
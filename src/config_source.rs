@@ -0,0 +1,353 @@
+use crate::config::{BasePathBehaviour, Config, HandlerConfig, ResponseType};
+use rocket::config::{Table, Value};
+use rocket::Rocket;
+use serde_json::{Map, Value as JsonValue};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// The prefix of the environment variables read by
+/// [load_config](crate::RocketHandlerBuilder::load_config).
+const ENV_PREFIX: &str = "ROCKET_LAMB_";
+
+/// The name of the table in Rocket's configuration read by
+/// [load_config](crate::RocketHandlerBuilder::load_config).
+const ROCKET_TABLE: &str = "lambda";
+
+/// An invalid setting found by [load_config](crate::RocketHandlerBuilder::load_config).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Where the setting was found, e.g. `ROCKET_LAMB_DEFAULT_RESPONSE_TYPE` or
+    /// `lambda.default_response_type`.
+    pub setting: String,
+    /// A description of why the setting is invalid.
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid setting {}: {}", self.setting, self.message)
+    }
+}
+
+impl Error for ConfigError {}
+
+fn config_error(setting: impl Into<String>, message: impl Into<String>) -> ConfigError {
+    ConfigError {
+        setting: setting.into(),
+        message: message.into(),
+    }
+}
+
+/// Records which [HandlerConfig] fields have been configured in code, so that they are not replaced by
+/// loaded settings.
+#[derive(Default)]
+pub(crate) struct CodeSettings {
+    fields: HashSet<String>,
+}
+
+impl CodeSettings {
+    /// Marks the `HandlerConfig` field named `field` as configured in code.
+    pub(crate) fn set(&mut self, field: &str) {
+        self.fields.insert(field.to_owned());
+    }
+
+    /// Marks every `HandlerConfig` field as configured in code.
+    pub(crate) fn set_all(&mut self) {
+        self.fields.extend(setting_names());
+    }
+
+    fn is_set(&self, field: &str) -> bool {
+        self.fields.contains(field)
+    }
+}
+
+/// Settings read from the environment or from Rocket's configuration. Unset settings are `None`.
+#[derive(Default)]
+struct LoadedSettings {
+    default_response_type: Option<ResponseType>,
+    response_types: HashMap<String, ResponseType>,
+    base_path_behaviour: Option<BasePathBehaviour>,
+    /// Every other setting, as the serialized value of the `HandlerConfig` field with the same name.
+    others: Map<String, JsonValue>,
+}
+
+impl LoadedSettings {
+    /// Replaces these settings with any that are set in `other`.
+    fn merge(&mut self, other: LoadedSettings) {
+        if other.default_response_type.is_some() {
+            self.default_response_type = other.default_response_type;
+        }
+        self.response_types.extend(other.response_types);
+        if other.base_path_behaviour.is_some() {
+            self.base_path_behaviour = other.base_path_behaviour;
+        }
+        for (key, value) in other.others {
+            match (self.others.get_mut(&key), value) {
+                (Some(JsonValue::Object(entries)), JsonValue::Object(other_entries))
+                    if is_map_setting(&key) =>
+                {
+                    entries.extend(other_entries)
+                }
+                (_, value) => {
+                    self.others.insert(key, value);
+                }
+            }
+        }
+    }
+}
+
+fn handler_config_object(config: &HandlerConfig) -> Map<String, JsonValue> {
+    match serde_json::to_value(config) {
+        Ok(JsonValue::Object(object)) => object,
+        _ => Map::new(),
+    }
+}
+
+/// The names of the `HandlerConfig` fields.
+fn setting_names() -> impl Iterator<Item = String> {
+    handler_config_object(&HandlerConfig::default())
+        .into_iter()
+        .map(|(key, _)| key)
+}
+
+/// Whether the `HandlerConfig` field named `key` is a map, e.g. `extension_methods`, rather than a struct.
+fn is_map_setting(key: &str) -> bool {
+    matches!(
+        handler_config_object(&HandlerConfig::default()).get(key),
+        Some(JsonValue::Object(_))
+    )
+}
+
+/// Checks that `value` can be deserialized as the `HandlerConfig` field named `key`, and adds it to `settings`.
+fn insert_other(
+    settings: &mut LoadedSettings,
+    setting: &str,
+    key: &str,
+    value: JsonValue,
+) -> Result<(), ConfigError> {
+    if !setting_names().any(|name| name == key) {
+        return Err(config_error(setting, "unknown setting"));
+    }
+    let mut object = Map::new();
+    object.insert(key.to_owned(), value);
+    serde_json::from_value::<HandlerConfig>(JsonValue::Object(object.clone()))
+        .map_err(|e| config_error(setting, e.to_string()))?;
+    settings.others.extend(object);
+    Ok(())
+}
+
+/// Parses an environment variable as a TOML value, as Rocket does, falling back to a string.
+fn parse_env_value(value: &str) -> JsonValue {
+    match format!("value = {}", value).parse::<Value>() {
+        Ok(Value::Table(mut table)) => table
+            .remove("value")
+            .and_then(|value| serde_json::to_value(value).ok())
+            .unwrap_or_else(|| JsonValue::String(value.to_owned())),
+        _ => JsonValue::String(value.to_owned()),
+    }
+}
+
+fn parse_response_type(setting: &str, value: &str) -> Result<ResponseType, ConfigError> {
+    match value.trim().to_lowercase().as_str() {
+        "auto" => Ok(ResponseType::Auto),
+        "text" => Ok(ResponseType::Text),
+        "binary" => Ok(ResponseType::Binary),
+        _ => Err(config_error(
+            setting,
+            format!(
+                "expected \"auto\", \"text\" or \"binary\", found \"{}\"",
+                value
+            ),
+        )),
+    }
+}
+
+fn parse_base_path_behaviour(setting: &str, value: &str) -> Result<BasePathBehaviour, ConfigError> {
+    match value.trim().to_lowercase().replace('-', "_").as_str() {
+        "remount_and_include" => Ok(BasePathBehaviour::RemountAndInclude),
+        "include" => Ok(BasePathBehaviour::Include),
+        "exclude" => Ok(BasePathBehaviour::Exclude),
+        _ => Err(config_error(
+            setting,
+            format!(
+                "expected \"remount_and_include\", \"include\" or \"exclude\", found \"{}\"",
+                value
+            ),
+        )),
+    }
+}
+
+/// Reads settings from `ROCKET_LAMB_*` environment variables. Response types are a semicolon-separated
+/// list of `content-type=response-type` pairs, e.g. `image/png=binary;text/csv=text`. Other settings are
+/// TOML values, e.g. `{ margin_ms = 500, status = 504 }`, or unquoted strings.
+///
+/// Rocket also parses every `ROCKET_*` variable as a configuration value, and fails to start if a value
+/// contains an unquoted comma, so commas cannot be used as the separator.
+fn from_env(vars: impl Iterator<Item = (String, String)>) -> Result<LoadedSettings, ConfigError> {
+    let mut settings = LoadedSettings::default();
+    for (name, value) in vars {
+        if !name.starts_with(ENV_PREFIX) {
+            continue;
+        }
+        match &name[ENV_PREFIX.len()..] {
+            "DEFAULT_RESPONSE_TYPE" => {
+                settings.default_response_type = Some(parse_response_type(&name, &value)?)
+            }
+            "RESPONSE_TYPES" => {
+                for pair in value.split(';').filter(|pair| !pair.trim().is_empty()) {
+                    let mut parts = pair.splitn(2, '=');
+                    let content_type = parts.next().unwrap_or_default().trim();
+                    let response_type = parts.next().ok_or_else(|| {
+                        config_error(
+                            &name,
+                            format!(
+                                "expected \"content-type=response-type\", found \"{}\"",
+                                pair
+                            ),
+                        )
+                    })?;
+                    settings.response_types.insert(
+                        content_type.to_lowercase(),
+                        parse_response_type(&name, response_type)?,
+                    );
+                }
+            }
+            "BASE_PATH_BEHAVIOUR" => {
+                settings.base_path_behaviour = Some(parse_base_path_behaviour(&name, &value)?)
+            }
+            key => {
+                let key = key.to_lowercase();
+                insert_other(&mut settings, &name, &key, parse_env_value(&value))?;
+            }
+        }
+    }
+    Ok(settings)
+}
+
+fn expect_str<'a>(setting: &str, value: &'a Value) -> Result<&'a str, ConfigError> {
+    value.as_str().ok_or_else(|| {
+        config_error(
+            setting,
+            format!("expected a string, found {}", value.type_str()),
+        )
+    })
+}
+
+/// Reads settings from the `[lambda]` table of Rocket's configuration.
+fn from_rocket_table(table: &Table) -> Result<LoadedSettings, ConfigError> {
+    let mut settings = LoadedSettings::default();
+    for (key, value) in table {
+        let setting = format!("{}.{}", ROCKET_TABLE, key);
+        match key.as_str() {
+            "default_response_type" => {
+                let value = expect_str(&setting, value)?;
+                settings.default_response_type = Some(parse_response_type(&setting, value)?);
+            }
+            "response_types" => {
+                let types = value.as_table().ok_or_else(|| {
+                    config_error(
+                        &setting,
+                        format!("expected a table, found {}", value.type_str()),
+                    )
+                })?;
+                for (content_type, response_type) in types {
+                    let setting = format!("{}.\"{}\"", setting, content_type);
+                    let response_type = expect_str(&setting, response_type)?;
+                    settings.response_types.insert(
+                        content_type.to_lowercase(),
+                        parse_response_type(&setting, response_type)?,
+                    );
+                }
+            }
+            "base_path_behaviour" => {
+                let value = expect_str(&setting, value)?;
+                settings.base_path_behaviour = Some(parse_base_path_behaviour(&setting, value)?);
+            }
+            key => {
+                let value = serde_json::to_value(value)
+                    .map_err(|e| config_error(&setting, e.to_string()))?;
+                insert_other(&mut settings, &setting, key, value)?;
+            }
+        }
+    }
+    Ok(settings)
+}
+
+/// Applies the settings from Rocket's configuration and the environment to `config`, except for
+/// settings that were configured in code. Environment variables take precedence over Rocket's configuration.
+///
+/// Loaded entries of maps (e.g. `extension_methods`) and lists (e.g. `redacted_headers`) are added to the
+/// configured ones, rather than replacing them.
+pub(crate) fn load(
+    rocket: &Rocket,
+    vars: impl Iterator<Item = (String, String)>,
+    config: &mut Config,
+    code_settings: &CodeSettings,
+) -> Result<(), ConfigError> {
+    let mut settings = match rocket.config().extras.get(ROCKET_TABLE) {
+        Some(Value::Table(table)) => from_rocket_table(table)?,
+        Some(value) => {
+            return Err(config_error(
+                ROCKET_TABLE,
+                format!("expected a table, found {}", value.type_str()),
+            ))
+        }
+        None => LoadedSettings::default(),
+    };
+    settings.merge(from_env(vars)?);
+
+    if let Some(response_type) = settings.default_response_type {
+        if !code_settings.is_set("default_response_type") {
+            config.default_response_type = response_type;
+        }
+    }
+    for (content_type, response_type) in settings.response_types {
+        // Response types set in code are already in the map, and are kept.
+        config
+            .response_types
+            .entry(content_type)
+            .or_insert(response_type);
+    }
+    if let Some(behaviour) = settings.base_path_behaviour {
+        if !code_settings.is_set("base_path_behaviour") {
+            config.base_path_behaviour = behaviour;
+        }
+    }
+    if settings.others.is_empty() {
+        return Ok(());
+    }
+
+    let mut handler_config = handler_config_object(&config.handler_config());
+    for (key, value) in settings.others {
+        match (handler_config.get_mut(&key), value) {
+            (Some(JsonValue::Object(entries)), JsonValue::Object(loaded))
+                if is_map_setting(&key) =>
+            {
+                // Entries set in code are kept.
+                for (entry, value) in loaded {
+                    let configured = entries.keys().any(|e| e.eq_ignore_ascii_case(&entry));
+                    if !configured {
+                        entries.insert(entry, value);
+                    }
+                }
+            }
+            (Some(JsonValue::Array(items)), JsonValue::Array(loaded)) => {
+                for item in loaded {
+                    if !items.contains(&item) {
+                        items.push(item);
+                    }
+                }
+            }
+            (_, value) => {
+                if !code_settings.is_set(&key) {
+                    handler_config.insert(key, value);
+                }
+            }
+        }
+    }
+    let handler_config = serde_json::from_value(JsonValue::Object(handler_config))
+        .map_err(|e| config_error(ROCKET_TABLE, e.to_string()))?;
+    config.set_handler_config(handler_config);
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use lambda_runtime::Context;
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Request guard for the time at which Lambda will stop the current invocation.
+///
+/// This guard forwards if the deadline of the invocation is not known, e.g. when the handler is
+/// called directly with a default `Context` in tests.
+///
+/// # Example
+///
+/// ```rust
+/// #![feature(proc_macro_hygiene, decl_macro)]
+/// # #[macro_use] extern crate rocket;
+/// use rocket_lamb::Deadline;
+/// use std::time::Duration;
+///
+/// #[get("/report")]
+/// fn report(deadline: Deadline) -> String {
+///     if deadline.remaining() < Duration::from_secs(5) {
+///         "Not enough time to generate the full report".to_owned()
+///     } else {
+///         "Full report".to_owned()
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Deadline(pub SystemTime);
+
+impl Deadline {
+    /// Gets the deadline of an invocation from its `Context`, if it is known.
+    pub(crate) fn from_context(ctx: &Context) -> Option<Deadline> {
+        if ctx.deadline > 0 {
+            Some(Deadline(
+                UNIX_EPOCH + Duration::from_millis(ctx.deadline as u64),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the time remaining until the deadline, or zero if it has passed.
+    pub fn remaining(&self) -> Duration {
+        self.0
+            .duration_since(SystemTime::now())
+            .unwrap_or_else(|_| Duration::from_secs(0))
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for Deadline {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match request.local_cache(|| None::<Deadline>) {
+            Some(deadline) => Outcome::Success(*deadline),
+            None => Outcome::Forward(()),
+        }
+    }
+}
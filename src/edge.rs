@@ -0,0 +1,185 @@
+use crate::encoding::normalize_path;
+use crate::error::RocketLambError;
+use lambda_http::{Body, Response};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Maximum size of a generated response body for viewer request events.
+const VIEWER_BODY_LIMIT: usize = 40 * 1024;
+/// Maximum size of a generated response body for origin request events.
+const ORIGIN_BODY_LIMIT: usize = 1024 * 1024;
+
+/// Headers that Lambda@Edge does not allow a function to set.
+const DISALLOWED_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "via",
+];
+
+/// Headers that describe the generated response rather than the request, e.g. those added by Rocket to
+/// every response, which are not copied into a request that continues to the origin.
+const RESPONSE_HEADERS: &[&str] = &["content-length", "content-type", "server"];
+
+#[derive(Deserialize)]
+pub(crate) struct EdgeEvent {
+    #[serde(rename = "Records")]
+    records: Vec<EdgeRecord>,
+}
+
+#[derive(Deserialize)]
+struct EdgeRecord {
+    cf: CloudFrontEvent,
+}
+
+#[derive(Deserialize)]
+struct CloudFrontEvent {
+    config: CloudFrontConfig,
+    request: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CloudFrontConfig {
+    #[serde(default)]
+    event_type: String,
+}
+
+/// A CloudFront request converted into the parts needed to build a Rocket request.
+pub(crate) struct EdgeRequest {
+    pub(crate) method: http::Method,
+    pub(crate) uri: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+    event_type: String,
+    raw: Value,
+}
+
+impl EdgeEvent {
+    pub(crate) fn into_request(self) -> Result<EdgeRequest, RocketLambError> {
+        let cf = self
+            .records
+            .into_iter()
+            .next()
+            .ok_or_else(|| invalid_request!("Lambda@Edge event contains no records"))?
+            .cf;
+        let request = cf.request;
+
+        let method = request["method"]
+            .as_str()
+            .and_then(|m| http::Method::from_bytes(m.as_bytes()).ok())
+            .ok_or_else(|| invalid_request!("missing or invalid CloudFront request method"))?;
+        let mut uri = normalize_path(
+            request["uri"]
+                .as_str()
+                .ok_or_else(|| invalid_request!("missing CloudFront request uri"))?,
+        );
+        match request["querystring"].as_str() {
+            Some(q) if !q.is_empty() => {
+                uri.push('?');
+                uri.push_str(q);
+            }
+            _ => {}
+        }
+
+        let mut headers = Vec::new();
+        if let Some(map) = request["headers"].as_object() {
+            for (name, entries) in map {
+                for entry in entries.as_array().into_iter().flatten() {
+                    let key = entry["key"].as_str().unwrap_or(name);
+                    if let Some(value) = entry["value"].as_str() {
+                        headers.push((key.to_owned(), value.to_owned()));
+                    }
+                }
+            }
+        }
+
+        let body = match request["body"]["data"].as_str() {
+            Some(data) if request["body"]["encoding"] == "base64" => base64::decode(data)
+                .map_err(|e| invalid_request!("invalid base-64 request body: {}", e))?,
+            Some(data) => data.as_bytes().to_vec(),
+            None => Vec::new(),
+        };
+
+        Ok(EdgeRequest {
+            method,
+            uri,
+            headers,
+            body,
+            event_type: cf.config.event_type,
+            raw: request,
+        })
+    }
+}
+
+impl EdgeRequest {
+    /// Builds the CloudFront response object that is returned to the viewer.
+    pub(crate) fn response(&self, res: &Response<Body>) -> Result<Value, RocketLambError> {
+        let (body, encoding) = match res.body() {
+            Body::Empty => (String::new(), "text"),
+            Body::Text(s) => (s.clone(), "text"),
+            Body::Binary(b) => (base64::encode(b), "base64"),
+        };
+        let limit = if self.event_type.starts_with("viewer") {
+            VIEWER_BODY_LIMIT
+        } else {
+            ORIGIN_BODY_LIMIT
+        };
+        if body.len() > limit {
+            return Err(invalid_response!(
+                "response body of {} bytes exceeds the Lambda@Edge limit of {} bytes for {} events",
+                body.len(),
+                limit,
+                self.event_type
+            ));
+        }
+
+        let mut value = json!({
+            "status": res.status().as_u16().to_string(),
+            "statusDescription": res.status().canonical_reason().unwrap_or_default(),
+            "headers": edge_headers(res, Map::new(), &[]),
+        });
+        if !body.is_empty() {
+            value["body"] = Value::String(body);
+            value["bodyEncoding"] = Value::String(encoding.to_owned());
+        }
+        Ok(value)
+    }
+
+    /// Returns the original CloudFront request so that it continues to the origin, with any
+    /// headers set on `res` added to (or replacing those in) the request. `Server`, `Content-Type`
+    /// and `Content-Length` describe the generated response, so they are not copied.
+    pub(crate) fn continue_to_origin(self, res: &Response<Body>) -> Value {
+        let mut request = self.raw;
+        let headers = request["headers"].as_object().cloned().unwrap_or_default();
+        request["headers"] = edge_headers(res, headers, RESPONSE_HEADERS);
+        request
+    }
+}
+
+/// Adds the headers of `res` to `headers`, except for those Lambda@Edge does not allow and those in `skipped`.
+fn edge_headers(res: &Response<Body>, mut headers: Map<String, Value>, skipped: &[&str]) -> Value {
+    for name in res.headers().keys() {
+        let name = name.as_str();
+        if DISALLOWED_HEADERS.contains(&name)
+            || skipped.contains(&name)
+            || name.starts_with("x-edge-")
+        {
+            continue;
+        }
+        let values = res
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|v| json!({ "key": name, "value": String::from_utf8_lossy(v.as_bytes()) }))
+            .collect();
+        headers.insert(name.to_owned(), Value::Array(values));
+    }
+    Value::Object(headers)
+}
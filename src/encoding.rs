@@ -0,0 +1,83 @@
+/// Percent-encodes everything except unreserved characters (RFC 3986 section 2.3), so that
+/// the result can be safely used as a key or value in a query string.
+pub(crate) fn encode_query_component(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+/// Appends a `key=value` pair to a query string, or just `key` if `value` is `None`.
+pub(crate) fn push_query_pair(query: &mut String, key: &str, value: Option<&str>) {
+    if !query.is_empty() {
+        query.push('&');
+    }
+    query.push_str(key);
+    if let Some(value) = value {
+        query.push('=');
+        query.push_str(value);
+    }
+}
+
+/// Normalises a request path into a valid, percent-encoded URI path.
+///
+/// Existing percent-encoded sequences are preserved as they are (including `%2F`, so that an
+/// encoded slash stays within a single segment), and any character that is not allowed in a URI
+/// path (e.g. spaces, non-ASCII characters, or a `%` that does not begin a valid sequence) is
+/// percent-encoded. This means the result is the same whether the integration passed the path
+/// encoded (e.g. Application Load Balancers) or decoded.
+pub(crate) fn normalize_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut normalized = String::with_capacity(bytes.len());
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'%' if is_escape(bytes, i) => normalized.push('%'),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => normalized.push(b as char),
+            b'-' | b'.' | b'_' | b'~' | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+'
+            | b',' | b';' | b'=' | b':' | b'@' | b'/' => normalized.push(b as char),
+            _ => normalized.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    normalized
+}
+
+/// Decodes a percent-encoded path for comparison with decoded values, such as API Gateway path
+/// parameters. Encoded slashes are kept as `%2F` so that segment boundaries are unchanged.
+pub(crate) fn decode_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && is_escape(bytes, i) {
+            let byte = hex_value(bytes[i + 1]) * 16 + hex_value(bytes[i + 2]);
+            if byte == b'/' {
+                decoded.extend_from_slice(b"%2F");
+            } else {
+                decoded.push(byte);
+            }
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn is_escape(bytes: &[u8], i: usize) -> bool {
+    i + 2 < bytes.len() && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit()
+}
+
+fn hex_value(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        _ => b - b'A' + 10,
+    }
+}
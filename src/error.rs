@@ -4,6 +4,16 @@ pub(crate) enum RocketLambError {
     InvalidRequest(String),
     #[fail(display = "could not transform response: {}", 0)]
     InvalidResponse(String),
+    #[fail(display = "route responded with status {}: {}", 0, 1)]
+    ErrorStatus(u16, String),
+    #[fail(display = "could not export telemetry: {}", 0)]
+    ExportFailed(String),
+    #[fail(display = "{} did not complete before the invocation's deadline", 0)]
+    Timeout(String),
+    #[fail(display = "{} panicked", 0)]
+    HandlerPanicked(String),
+    #[fail(display = "the handler of a previous invocation that overran its deadline was lost")]
+    HandlerLost,
 }
 
 macro_rules! invalid_request {
@@ -13,3 +23,7 @@ macro_rules! invalid_request {
 macro_rules! invalid_response {
     ($($arg:tt)*) => (RocketLambError::InvalidResponse(format!($($arg)*)))
 }
+
+macro_rules! export_failed {
+    ($($arg:tt)*) => (RocketLambError::ExportFailed(format!($($arg)*)))
+}
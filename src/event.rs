@@ -0,0 +1,130 @@
+use crate::edge::EdgeEvent;
+use crate::encoding::normalize_path;
+use crate::error::RocketLambError;
+use crate::http_api;
+use crate::lattice::LatticeEvent;
+use crate::request_ext::QueryString;
+use crate::response::ResponseFormat;
+use crate::websocket::WebSocketEvent;
+use lambda_http::Request;
+use serde_json::{Map, Value};
+
+/// An incoming Lambda event, classified by the service that sent it.
+pub(crate) enum LambdaEvent {
+    /// An API Gateway (REST or HTTP API) or Application Load Balancer request, along with the
+    /// format that the response must be returned in.
+    Http(Request, ResponseFormat),
+    /// A message from an API Gateway WebSocket API.
+    WebSocket(WebSocketEvent),
+    /// A CloudFront viewer request or origin request event for Lambda@Edge.
+    Edge(EdgeEvent),
+    /// A request from a VPC Lattice service.
+    Lattice(LatticeEvent),
+    /// Any other payload, e.g. from a Step Functions task or a direct `Invoke` call.
+    Direct(Value),
+}
+
+impl LambdaEvent {
+    /// Describes the event for log messages, e.g. `GET /path` for an HTTP request.
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            LambdaEvent::Http(req, _) => format!("{} {}", req.method(), req.uri().path()),
+            LambdaEvent::WebSocket(_) => "WebSocket message".to_owned(),
+            LambdaEvent::Edge(_) => "Lambda@Edge request".to_owned(),
+            LambdaEvent::Lattice(_) => "VPC Lattice request".to_owned(),
+            LambdaEvent::Direct(_) => "direct invocation".to_owned(),
+        }
+    }
+
+    pub(crate) fn from_value(mut event: Value) -> Result<LambdaEvent, RocketLambError> {
+        if is_websocket_event(&event) {
+            serde_json::from_value(event)
+                .map(LambdaEvent::WebSocket)
+                .map_err(|e| invalid_request!("invalid WebSocket event: {}", e))
+        } else if is_edge_event(&event) {
+            serde_json::from_value(event)
+                .map(LambdaEvent::Edge)
+                .map_err(|e| invalid_request!("invalid Lambda@Edge event: {}", e))
+        } else if is_lattice_event(&event) {
+            serde_json::from_value(event)
+                .map(LambdaEvent::Lattice)
+                .map_err(|e| invalid_request!("invalid VPC Lattice event: {}", e))
+        } else if !is_http_event(&event) {
+            Ok(LambdaEvent::Direct(event))
+        } else {
+            let format = ResponseFormat::detect(&event);
+            normalize_path_field(&mut event, "path");
+            normalize_path_field(&mut event, "rawPath");
+            let raw_query = if format == ResponseFormat::HttpApiV2 {
+                let raw_query = event["rawQueryString"].as_str().unwrap_or_default();
+                let raw_query = QueryString(raw_query.to_owned());
+                http_api::convert_to_v1(&mut event);
+                Some(raw_query)
+            } else {
+                None
+            };
+            if let ResponseFormat::Alb { multi_value: true } = format {
+                fill_single_values(&mut event, "headers", "multiValueHeaders");
+                fill_single_values(
+                    &mut event,
+                    "queryStringParameters",
+                    "multiValueQueryStringParameters",
+                );
+            }
+            lambda_http::request::from_str(&event.to_string())
+                .map(|mut req| {
+                    if let Some(raw_query) = raw_query {
+                        req.extensions_mut().insert(raw_query);
+                    }
+                    LambdaEvent::Http(req, format)
+                })
+                .map_err(|e| invalid_request!("{}", e))
+        }
+    }
+}
+
+fn is_websocket_event(event: &Value) -> bool {
+    let context = &event["requestContext"];
+    context["connectionId"].is_string() && context["routeKey"].is_string()
+}
+
+fn is_edge_event(event: &Value) -> bool {
+    event["Records"][0]["cf"]["request"].is_object()
+}
+
+fn is_lattice_event(event: &Value) -> bool {
+    event["raw_path"].is_string() && event["method"].is_string()
+}
+
+fn is_http_event(event: &Value) -> bool {
+    (event["httpMethod"].is_string() && event["requestContext"].is_object())
+        || http_api::is_http_api_v2_event(event)
+}
+
+/// API Gateway REST APIs may pass the path decoded, whereas other integrations pass it exactly as
+/// the client sent it. `lambda_http` cannot parse a path containing e.g. a space, so it is
+/// normalised before the event is parsed. Paths are normalised again when the request is processed,
+/// which also covers requests parsed by `lambda_http` itself.
+fn normalize_path_field(event: &mut Value, key: &str) {
+    if let Some(path) = event[key].as_str() {
+        event[key] = Value::String(normalize_path(path));
+    }
+}
+
+/// Target groups with multi-value headers enabled only send the `multiValue*` fields, so this
+/// fills in the single-value field from the last of each key's values.
+fn fill_single_values(event: &mut Value, single_key: &str, multi_key: &str) {
+    if event[single_key].is_object() {
+        return;
+    }
+    let single: Map<String, Value> = event[multi_key]
+        .as_object()
+        .map(|multi| {
+            multi
+                .iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_array()?.last()?.clone())))
+                .collect()
+        })
+        .unwrap_or_default();
+    event[single_key] = Value::Object(single);
+}
@@ -1,17 +1,50 @@
+use crate::access_log::{self, AccessLogEntry};
 use crate::config::*;
+use crate::deadline::Deadline;
+use crate::edge::EdgeEvent;
+use crate::encoding::{encode_query_component, push_query_pair};
 use crate::error::RocketLambError;
-use crate::request_ext::RequestExt as _;
+use crate::event::LambdaEvent;
+use crate::lattice::{self, LatticeEvent};
+use crate::method::{self, OriginalMethod};
+use crate::otel::{self, AttributeValue, Span, SpanContext};
+use crate::params::{self, StageVariables};
+use crate::recording::Recorder;
+use crate::request_ext::{QueryString, RequestExt as _};
+use crate::response::{self, ResponseFormat};
+use crate::route::{self, ExpectedRoute, MatchedRoute};
+use crate::websocket::WebSocketEvent;
+use crate::xray::TraceContext;
+use lambda_http::request::RequestContext;
 use lambda_http::{Body, Handler, Request, RequestExt, Response};
 use lambda_runtime::{error::HandlerError, Context};
-use rocket::http::{uri::Uri, Header};
+use rocket::http::{ContentType, Header, Method, StatusClass};
 use rocket::local::{Client, LocalRequest, LocalResponse};
 use rocket::{Rocket, Route};
+use serde_json::Value;
 use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// A Lambda handler for API Gateway events that processes requests using a [Rocket](rocket::Rocket) instance.
+///
+/// As well as implementing [lambda_http::Handler](lambda_http::Handler) for API Gateway and
+/// Application Load Balancer requests, this implements
+/// [lambda_runtime::Handler](lambda_runtime::Handler) for raw JSON events, which additionally
+/// supports API Gateway WebSocket APIs, VPC Lattice, Lambda@Edge and direct invocations. See
+/// [WebSocketConnection](crate::WebSocketConnection) for how WebSocket events are mapped onto
+/// Rocket routes, and [direct_invocation_route](crate::RocketHandlerBuilder::direct_invocation_route)
+/// for how other events are handled.
 pub struct RocketHandler {
     pub(super) client: LazyClient,
     pub(super) config: Config,
+    pub(super) context: Context,
+    pub(super) overrunning: Option<Receiver<RocketHandler>>,
+    /// The route expected to handle the request being dispatched, which is logged if it overruns.
+    pub(super) current_route: Arc<Mutex<Option<MatchedRoute>>>,
 }
 
 pub(super) enum LazyClient {
@@ -21,16 +54,449 @@ pub(super) enum LazyClient {
 }
 
 impl Handler<Response<Body>> for RocketHandler {
-    fn run(&mut self, req: Request, _ctx: Context) -> Result<Response<Body>, HandlerError> {
-        self.ensure_client_ready(&req);
-        self.process_request(req)
-            .map_err(failure::Error::from)
-            .map_err(failure::Error::into)
+    fn run(&mut self, req: Request, ctx: Context) -> Result<Response<Body>, HandlerError> {
+        self.start_invocation(&ctx);
+        let description = format!("{} {}", req.method(), req.uri().path());
+        self.with_watchdog(
+            description,
+            |res, _| Ok(res),
+            move |handler| handler.handle_request(req),
+        )
+        .map_err(failure::Error::from)
+        .map_err(failure::Error::into)
+    }
+}
+
+impl lambda_runtime::Handler<Value, Value> for RocketHandler {
+    fn run(&mut self, event: Value, ctx: Context) -> Result<Value, HandlerError> {
+        // The handler is replaced by a placeholder if the invocation overruns, so the recorder
+        // takes everything it needs beforehand.
+        let recorder = self.config.recording.clone().map(|sink| {
+            Recorder::new(
+                sink,
+                &self.config,
+                ctx.aws_request_id.clone(),
+                event.clone(),
+            )
+        });
+        let result = self.run_json(event, ctx);
+        if let Some(recorder) = recorder {
+            recorder.record(&result);
+        }
+        result
+    }
+}
+
+/// How a timeout response is returned for each kind of event.
+#[derive(Clone, Copy)]
+enum TimeoutResponse {
+    Http(ResponseFormat),
+    Lattice,
+    Error,
+}
+
+impl TimeoutResponse {
+    /// Converts the watchdog's response, where `overrunning` describes what did not complete in time.
+    fn to_json(self, res: Response<Body>, overrunning: String) -> Result<Value, RocketLambError> {
+        match self {
+            TimeoutResponse::Http(format) => Ok(response::to_json(&res, format)),
+            TimeoutResponse::Lattice => Ok(lattice::to_json(&res)),
+            TimeoutResponse::Error => Err(RocketLambError::Timeout(overrunning)),
+        }
     }
 }
 
 impl RocketHandler {
-    fn ensure_client_ready(&mut self, req: &Request) {
+    /// Gets the effective settings of the handler, e.g. to log them at cold start.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::{ResponseType, RocketExt};
+    ///
+    /// let handler = rocket::ignite()
+    ///     .lambda()
+    ///     .response_type("image/png", ResponseType::Binary)
+    ///     .into_handler();
+    ///
+    /// let config = handler.config();
+    /// assert_eq!(config.response_types["image/png"], ResponseType::Binary);
+    /// println!("{}", serde_json::to_string(&config).unwrap());
+    /// ```
+    pub fn config(&self) -> HandlerConfig {
+        self.config.handler_config()
+    }
+
+    /// A handler with no Rocket, which takes the place of a handler that is processing an
+    /// event on a watchdog thread. It keeps the watchdog settings, so that later invocations
+    /// still respond before their deadline while the handler is unavailable.
+    fn placeholder(&self) -> RocketHandler {
+        RocketHandler {
+            client: LazyClient::Placeholder,
+            config: Config {
+                deadline_watchdog: self.config.deadline_watchdog,
+                ..Config::default()
+            },
+            context: self.context.clone(),
+            overrunning: None,
+            current_route: self.current_route.clone(),
+        }
+    }
+
+    /// Handles a raw JSON event, as received by `lambda_runtime`.
+    fn run_json(&mut self, event: Value, ctx: Context) -> Result<Value, HandlerError> {
+        self.start_invocation(&ctx);
+        let event = LambdaEvent::from_value(event).map_err(failure::Error::from)?;
+        let description = event.describe();
+        let on_timeout = match &event {
+            LambdaEvent::Http(_, format) => TimeoutResponse::Http(*format),
+            LambdaEvent::WebSocket(_) => TimeoutResponse::Http(ResponseFormat::ApiGateway),
+            LambdaEvent::Lattice(_) => TimeoutResponse::Lattice,
+            LambdaEvent::Edge(_) | LambdaEvent::Direct(_) => TimeoutResponse::Error,
+        };
+        self.with_watchdog(
+            description,
+            move |res, overrunning| on_timeout.to_json(res, overrunning),
+            move |handler| handler.handle_event(event),
+        )
+        .map_err(failure::Error::from)
+        .map_err(failure::Error::into)
+    }
+
+    /// Prepares to handle a new invocation.
+    fn start_invocation(&mut self, ctx: &Context) {
+        self.context = ctx.clone();
+        *self.current_route.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Takes back the handler of an invocation that overran its deadline, keeping the context of
+    /// the current invocation.
+    fn restore(&mut self, mut handler: RocketHandler) {
+        handler.context = mem::take(&mut self.context);
+        *self = handler;
+    }
+
+    /// Gets the time until the deadline watchdog responds to the current invocation, along with the
+    /// configured margin and status, if the watchdog is configured and the deadline is known.
+    fn watchdog(&self) -> Option<(Duration, Duration, u16)> {
+        let (margin, status) = self.config.deadline_watchdog?;
+        let deadline = Deadline::from_context(&self.context)?;
+        let timeout = deadline
+            .remaining()
+            .checked_sub(margin)
+            .unwrap_or_else(|| Duration::from_secs(0));
+        Some((timeout, margin, status))
+    }
+
+    /// Runs `work` on this handler. If a deadline watchdog is configured and the deadline of the
+    /// invocation is known, `work` runs on another thread, and if it does not complete in time,
+    /// a response with the configured status is passed to `on_timeout` instead.
+    ///
+    /// If a previous invocation is still running, this waits for it to finish so that its handler
+    /// can be reused, but no longer than the watchdog allows for this invocation.
+    fn with_watchdog<T, F>(
+        &mut self,
+        description: String,
+        on_timeout: impl FnOnce(Response<Body>, String) -> Result<T, RocketLambError>,
+        work: F,
+    ) -> Result<T, RocketLambError>
+    where
+        F: FnOnce(&mut RocketHandler) -> Result<T, RocketLambError> + Send + 'static,
+        T: Send + 'static,
+    {
+        if let Some(overrunning) = self.overrunning.take() {
+            let watchdog = self.watchdog();
+            let received = match watchdog {
+                Some((timeout, ..)) => overrunning.recv_timeout(timeout),
+                None => overrunning
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match (received, watchdog) {
+                (Ok(handler), _) => self.restore(handler),
+                (Err(RecvTimeoutError::Timeout), Some((.., status))) => {
+                    eprintln!(
+                        "{} could not start before its deadline, as a previous invocation is still running, responding with status {}",
+                        description, status
+                    );
+                    self.overrunning = Some(overrunning);
+                    return on_timeout(timeout_response(status)?, description);
+                }
+                _ => return Err(RocketLambError::HandlerLost),
+            }
+        }
+
+        let (timeout, margin, status) = match self.watchdog() {
+            Some(watchdog) => watchdog,
+            None => return work(self),
+        };
+
+        // The handler is sent back before the result, so it is always available once the result
+        // is. Panics are caught, so that the handler can be reused by later invocations.
+        let placeholder = self.placeholder();
+        let mut handler = mem::replace(self, placeholder);
+        let (handler_sender, handler_receiver) = mpsc::channel();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let panic_description = description.clone();
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| work(&mut handler)))
+                .unwrap_or_else(|_| Err(RocketLambError::HandlerPanicked(panic_description)));
+            let _ = handler_sender.send(handler);
+            let _ = result_sender.send(result);
+        });
+        match result_receiver.recv_timeout(timeout) {
+            Ok(result) => {
+                if let Ok(handler) = handler_receiver.recv() {
+                    self.restore(handler);
+                }
+                result
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let overrunning = self
+                    .current_route
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .as_ref()
+                    .map(MatchedRoute::id)
+                    .unwrap_or(description);
+                eprintln!(
+                    "{} did not complete within {}ms of the invocation's deadline, responding with status {}",
+                    overrunning,
+                    margin.as_millis(),
+                    status
+                );
+                self.overrunning = Some(handler_receiver);
+                on_timeout(timeout_response(status)?, overrunning)
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                if let Ok(handler) = handler_receiver.try_recv() {
+                    self.restore(handler);
+                }
+                Err(RocketLambError::HandlerPanicked(description))
+            }
+        }
+    }
+
+    fn handle_request(&mut self, mut req: Request) -> Result<Response<Body>, RocketLambError> {
+        let start = Instant::now();
+        let start_time = SystemTime::now();
+        let cold_start = matches!(self.client, LazyClient::Uninitialized(_));
+
+        let mut hook_response = None;
+        for hook in &self.config.before_request_hooks {
+            hook_response = hook(&mut req, &self.context);
+            if hook_response.is_some() {
+                break;
+            }
+        }
+        let telemetry = self.begin_telemetry(&mut req, start, start_time, cold_start);
+
+        let res = match hook_response {
+            Some(res) => res,
+            None => {
+                self.ensure_client_ready(
+                    || req.base_path(),
+                    || {
+                        params::api_gateway_params(&req)
+                            .map(|(_, v)| v)
+                            .unwrap_or_default()
+                    },
+                )?;
+                self.process_request(req)?
+            }
+        };
+
+        self.record_telemetry(telemetry, &res);
+        Ok(res)
+    }
+
+    /// Captures the details of a request needed for access logs, metrics and traces, before the
+    /// request is processed. If OpenTelemetry tracing is enabled, the context of the request's
+    /// span is added to the request's extensions.
+    fn begin_telemetry(
+        &self,
+        req: &mut Request,
+        start: Instant,
+        start_time: SystemTime,
+        cold_start: bool,
+    ) -> RequestTelemetry {
+        let log_entry = match self.config.access_log {
+            Some(_) => Some(self.access_log_entry(req, cold_start)),
+            None => None,
+        };
+        let stage = match (&self.config.metrics, req.request_context()) {
+            (Some(_), RequestContext::ApiGateway { stage, .. }) => stage,
+            _ => String::new(),
+        };
+
+        let xray_header = req
+            .headers()
+            .get("X-Amzn-Trace-Id")
+            .and_then(|h| h.to_str().ok());
+        let trace = match (&self.config.xray, &self.config.span_exporter) {
+            (None, None) => None,
+            _ => TraceContext::find(self.xray_trace_id(), xray_header),
+        };
+        let span = match self.config.span_exporter {
+            Some(_) => {
+                let traceparent = req
+                    .headers()
+                    .get("traceparent")
+                    .and_then(|h| h.to_str().ok());
+                let parent = otel::parent_context(traceparent, trace.as_ref());
+                let context = otel::new_span_context(parent.as_ref());
+                req.extensions_mut().insert(context.clone());
+                Some((context, parent.map(|p| p.span_id)))
+            }
+            None => None,
+        };
+
+        RequestTelemetry {
+            start,
+            start_time,
+            cold_start,
+            method: req.method().to_string(),
+            url: req.uri().to_string(),
+            log_entry,
+            stage,
+            trace: trace.filter(|_| self.config.xray.is_some()),
+            span,
+        }
+    }
+
+    /// Records the access log entry, metrics and traces for a request once it has been processed.
+    fn record_telemetry(&self, telemetry: RequestTelemetry, res: &Response<Body>) {
+        if let (Some(sink), Some(mut entry)) = (&self.config.access_log, telemetry.log_entry) {
+            entry.status = res.status().as_u16();
+            entry.response_type = match res.body() {
+                Body::Empty => None,
+                Body::Text(_) => Some(ResponseType::Text),
+                Body::Binary(_) => Some(ResponseType::Binary),
+            };
+            entry.response_body_size = res.body().len();
+            entry.duration = telemetry.start.elapsed();
+            sink.log(&entry);
+        }
+        if let Some(metrics) = &self.config.metrics {
+            let latency = telemetry.start.elapsed();
+            metrics.record(&telemetry.stage, res, latency, telemetry.cold_start);
+        }
+        if let (Some(xray), Some(trace)) = (&self.config.xray, &telemetry.trace) {
+            xray.emit(
+                trace,
+                &telemetry.method,
+                &telemetry.url,
+                telemetry.start_time,
+                res,
+            );
+        }
+        // Unsampled traces are not exported, following the sampling decision of the caller.
+        if let (Some(exporter), Some((context, parent_span_id))) = (
+            &self.config.span_exporter,
+            telemetry.span.filter(|(context, _)| context.sampled),
+        ) {
+            let route = res.extensions().get::<MatchedRoute>();
+            let status = res.status().as_u16();
+            let mut attributes = vec![
+                attribute("http.request.method", telemetry.method.as_str()),
+                attribute("http.response.status_code", i64::from(status)),
+                attribute("url.full", telemetry.url.as_str()),
+                attribute("faas.trigger", "http"),
+                attribute("faas.invocation_id", self.context.aws_request_id.as_str()),
+                attribute("faas.coldstart", telemetry.cold_start),
+            ];
+            let name = match route {
+                Some(route) => {
+                    attributes.push(attribute("http.route", route.uri.as_str()));
+                    format!("{} {}", telemetry.method, route.uri)
+                }
+                None => telemetry.method.clone(),
+            };
+            exporter.export(&Span {
+                name,
+                context,
+                parent_span_id,
+                start_time: telemetry.start_time,
+                end_time: SystemTime::now(),
+                attributes,
+                is_error: status >= 500,
+            });
+        }
+    }
+
+    fn xray_trace_id(&self) -> Option<&str> {
+        self.context.xray_trace_id.as_ref().map(String::as_str)
+    }
+
+    /// Creates an access log entry for a request, with its response fields left empty.
+    fn access_log_entry(&self, req: &Request, cold_start: bool) -> AccessLogEntry {
+        let remote_addr = match req.request_context() {
+            RequestContext::ApiGateway { identity, .. } => Some(identity.source_ip),
+            RequestContext::Alb { .. } => req
+                .headers()
+                .get("x-forwarded-for")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.split(',').next())
+                .map(|ip| ip.trim().to_owned()),
+        };
+        let headers = req
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let redacted = self
+                    .config
+                    .redacted_headers
+                    .iter()
+                    .any(|h| h.eq_ignore_ascii_case(name.as_str()));
+                let value = if redacted {
+                    access_log::REDACTED.to_owned()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.to_string(), value)
+            })
+            .collect();
+
+        AccessLogEntry {
+            request_id: self.context.aws_request_id.clone(),
+            time: SystemTime::now(),
+            remote_addr: remote_addr.filter(|ip| !ip.is_empty()),
+            method: req.method().to_string(),
+            path: access_log::redact_query(
+                &self.try_get_path_and_query(req).unwrap_or_else(|| {
+                    with_query(req.uri().path().to_owned(), &self.get_query(req))
+                }),
+                &self.config.redacted_query_parameters,
+            ),
+            original_path: req.uri().path().to_owned(),
+            base_path: req.try_base_path().unwrap_or_default(),
+            headers,
+            status: 0,
+            response_type: None,
+            request_body_size: req.body().len(),
+            response_body_size: 0,
+            cold_start,
+            duration: Duration::from_secs(0),
+        }
+    }
+
+    fn handle_event(&mut self, event: LambdaEvent) -> Result<Value, RocketLambError> {
+        match event {
+            LambdaEvent::Http(req, format) => self
+                .handle_request(req)
+                .map(|res| response::to_json(&res, format)),
+            event => {
+                self.ensure_client_ready(String::new, StageVariables::default)?;
+                self.process_event(event)
+            }
+        }
+    }
+
+    fn ensure_client_ready(
+        &mut self,
+        base_path: impl FnOnce() -> String,
+        stage_variables: impl FnOnce() -> StageVariables,
+    ) -> Result<(), RocketLambError> {
         match self.client {
             ref mut lazy_client @ LazyClient::Uninitialized(_) => {
                 let uninitialized_client = mem::replace(lazy_client, LazyClient::Placeholder);
@@ -38,8 +504,14 @@ impl RocketHandler {
                     LazyClient::Uninitialized(rocket) => rocket,
                     _ => unreachable!("LazyClient must be uninitialized at this point."),
                 };
+                for hook in self.config.cold_start_hooks.drain(..) {
+                    rocket = hook(rocket, &self.context);
+                }
+                if let Some(hook) = self.config.stage_variables_hook.take() {
+                    rocket = hook(rocket, &stage_variables());
+                }
                 if self.config.base_path_behaviour == BasePathBehaviour::RemountAndInclude {
-                    let base_path = req.base_path();
+                    let base_path = base_path();
                     if !base_path.is_empty() {
                         let routes: Vec<Route> = rocket.routes().cloned().collect();
                         rocket = rocket.mount(&base_path, routes);
@@ -49,8 +521,16 @@ impl RocketHandler {
                 self.client = LazyClient::Ready(client);
             }
             LazyClient::Ready(_) => {}
-            LazyClient::Placeholder => panic!("LazyClient has previously begun initialiation."),
+            // The Rocket is lost if initialisation or an overrunning invocation panicked.
+            LazyClient::Placeholder => {
+                return Err(RocketLambError::ErrorStatus(
+                    500,
+                    "the Rocket client is unavailable, as a previous invocation panicked"
+                        .to_owned(),
+                ))
+            }
         }
+        Ok(())
     }
 
     fn client(&self) -> &Client {
@@ -61,28 +541,307 @@ impl RocketHandler {
     }
 
     fn process_request(&self, req: Request) -> Result<Response<Body>, RocketLambError> {
-        let local_req = self.create_rocket_request(req)?;
-        let local_res = local_req.dispatch();
-        self.create_lambda_response(local_res)
-    }
+        if self.config.path_normalization == PathNormalization::Redirect {
+            let full_path = req.full_path();
+            let canonical_path = PathNormalization::Redirect.apply(&full_path);
+            if canonical_path != full_path {
+                let location = with_query(canonical_path, &self.get_query(&req));
+                return Response::builder()
+                    .status(308)
+                    .header(http::header::LOCATION, location)
+                    .body(Body::Empty)
+                    .map_err(|e| invalid_response!("{}", e));
+            }
+        }
 
-    fn create_rocket_request(&self, req: Request) -> Result<LocalRequest, RocketLambError> {
-        let method = to_rocket_method(req.method())?;
         let uri = self.get_path_and_query(&req);
-        let mut local_req = self.client().req(method, uri);
+        let params = params::api_gateway_params(&req);
+        let span_context = req.extensions().get::<SpanContext>().cloned();
+        // Like every other path, the fallback path is relative to the base path if it is included.
+        let base_path = || match self.config.base_path_behaviour {
+            BasePathBehaviour::Include | BasePathBehaviour::RemountAndInclude => req.base_path(),
+            BasePathBehaviour::Exclude => String::new(),
+        };
+        let target = match self.rocket_target(req.method(), uri.clone(), base_path)? {
+            Some(target) => target,
+            None => return self.method_not_allowed(&uri),
+        };
+        let local_req = self.create_rocket_request(target, req)?;
+        if let Some((path_parameters, stage_variables)) = params {
+            local_req.inner().local_cache(|| Some(path_parameters));
+            local_req.inner().local_cache(|| Some(stage_variables));
+        }
+        if let Some(span_context) = span_context {
+            local_req.inner().local_cache(|| Some(span_context));
+        }
+        self.dispatch(local_req)
+    }
+
+    /// Determines the method and URI that a request is dispatched to Rocket with. Requests with methods that
+    /// Rocket does not support are handled according to their [ExtensionMethodBehaviour], and `None` is
+    /// returned if the request is rejected. `base_path` is prepended to the path of a `Fallback` route.
+    fn rocket_target(
+        &self,
+        method: &http::Method,
+        uri: String,
+        base_path: impl FnOnce() -> String,
+    ) -> Result<Option<RocketTarget>, RocketLambError> {
+        if let Ok(method) = to_rocket_method(method) {
+            return Ok(Some(RocketTarget {
+                method,
+                uri,
+                headers: Vec::new(),
+                original_method: None,
+            }));
+        }
+        let original_method = method.to_string();
+        let behaviour = self
+            .config
+            .extension_methods
+            .get(&original_method.to_uppercase())
+            .unwrap_or(&self.config.default_extension_method);
+        let original_method_header = ("X-Original-Method".to_owned(), original_method.clone());
+        let target = match behaviour {
+            ExtensionMethodBehaviour::Reject => return Ok(None),
+            ExtensionMethodBehaviour::MapTo(method) => RocketTarget {
+                method: *method,
+                uri,
+                headers: vec![original_method_header],
+                original_method: Some(original_method),
+            },
+            ExtensionMethodBehaviour::Fallback(path) => {
+                let query = uri.splitn(2, '?').nth(1).unwrap_or_default();
+                let fallback_uri = with_query(format!("{}{}", base_path(), path), query);
+                RocketTarget {
+                    method: Method::Post,
+                    uri: fallback_uri,
+                    headers: vec![original_method_header, ("X-Original-Uri".to_owned(), uri)],
+                    original_method: Some(original_method),
+                }
+            }
+        };
+        Ok(Some(target))
+    }
+
+    /// Responds with `405 Method Not Allowed`. The `Allow` header lists the methods of the routes whose path
+    /// matches the request, and is omitted if there are none.
+    fn method_not_allowed(&self, uri: &str) -> Result<Response<Body>, RocketLambError> {
+        let path = uri.split('?').next().unwrap_or_default();
+        let allowed: Vec<&str> = method::allowed_methods(self.client().rocket().routes(), path)
+            .iter()
+            .map(|m| m.as_str())
+            .collect();
+        let mut builder = Response::builder();
+        builder.status(405);
+        if !allowed.is_empty() {
+            builder.header(http::header::ALLOW, allowed.join(", "));
+        }
+        builder
+            .body(Body::Empty)
+            .map_err(|e| invalid_response!("{}", e))
+    }
+
+    fn process_event(&self, event: LambdaEvent) -> Result<Value, RocketLambError> {
+        match event {
+            LambdaEvent::Http(..) => unreachable!("HTTP events are processed by process_request"),
+            LambdaEvent::WebSocket(event) => self
+                .process_websocket_event(event)
+                .map(|res| response::to_json(&res, ResponseFormat::ApiGateway)),
+            LambdaEvent::Lattice(event) => self
+                .process_lattice_event(event)
+                .map(|res| lattice::to_json(&res)),
+            LambdaEvent::Edge(event) => self.process_edge_event(event),
+            LambdaEvent::Direct(payload) => self.process_direct_invocation(payload),
+        }
+    }
+
+    fn process_websocket_event(
+        &self,
+        event: WebSocketEvent,
+    ) -> Result<Response<Body>, RocketLambError> {
+        let ws_req = event.into_request()?;
+        let local_req =
+            self.create_local_request(Method::Post, ws_req.uri, ws_req.headers, ws_req.body);
+        let connection = ws_req.connection;
+        local_req.inner().local_cache(|| Some(connection));
+        self.dispatch(local_req)
+    }
+
+    fn process_lattice_event(
+        &self,
+        event: LatticeEvent,
+    ) -> Result<Response<Body>, RocketLambError> {
+        let lattice_req = event.into_request()?;
+        let target =
+            match self.rocket_target(&lattice_req.method, lattice_req.uri.clone(), String::new)? {
+                Some(target) => target,
+                None => return self.method_not_allowed(&lattice_req.uri),
+            };
+        let local_req = self.create_target_request(target, lattice_req.headers, lattice_req.body);
+        self.dispatch(local_req)
+    }
+
+    fn process_edge_event(&self, event: EdgeEvent) -> Result<Value, RocketLambError> {
+        let edge_req = event.into_request()?;
+        let res = match self.rocket_target(&edge_req.method, edge_req.uri.clone(), String::new)? {
+            Some(target) => {
+                let local_req =
+                    self.create_target_request(target, edge_req.headers.clone(), &edge_req.body);
+                self.dispatch(local_req)?
+            }
+            None => self.method_not_allowed(&edge_req.uri)?,
+        };
+
+        if self.config.edge_continue_status == Some(res.status().as_u16()) {
+            Ok(edge_req.continue_to_origin(&res))
+        } else {
+            edge_req.response(&res)
+        }
+    }
+
+    fn process_direct_invocation(&self, payload: Value) -> Result<Value, RocketLambError> {
+        let route = self
+            .config
+            .direct_invocation_route
+            .as_ref()
+            .ok_or_else(|| {
+                invalid_request!(
+                    "event is not an HTTP request, and no direct invocation route is configured"
+                )
+            })?;
+        let mut local_req = self.client().post(route.as_str());
+        local_req.add_header(ContentType::JSON);
+        local_req.set_body(payload.to_string());
+        if let Some(deadline) = Deadline::from_context(&self.context) {
+            local_req.inner().local_cache(|| Some(deadline));
+        }
+        let mut local_res = local_req.dispatch();
+
+        let status = local_res.status();
+        let body = local_res.body_bytes().unwrap_or_default();
+        if status.class() != StatusClass::Success {
+            return Err(RocketLambError::ErrorStatus(
+                status.code,
+                String::from_utf8_lossy(&body).into_owned(),
+            ));
+        }
+        if body.is_empty() {
+            Ok(Value::Null)
+        } else {
+            serde_json::from_slice(&body)
+                .map_err(|e| invalid_response!("response body is not valid JSON: {}", e))
+        }
+    }
+
+    /// Creates a `LocalRequest` for `target` from an HTTP request.
+    fn create_rocket_request(
+        &self,
+        target: RocketTarget,
+        req: Request,
+    ) -> Result<LocalRequest, RocketLambError> {
+        let mut headers = Vec::with_capacity(req.headers().len());
         for (name, value) in req.headers() {
             match value.to_str() {
-                Ok(v) => local_req.add_header(Header::new(name.to_string(), v.to_string())),
+                Ok(v) => headers.push((name.to_string(), v.to_string())),
                 Err(_) => return Err(invalid_request!("invalid value for header '{}'", name)),
             }
         }
-        local_req.set_body(req.into_body());
-        Ok(local_req)
+        Ok(self.create_target_request(target, headers, req.into_body()))
+    }
+
+    /// Creates a `LocalRequest` for `target`. The target's headers are added to the request, replacing any
+    /// headers with the same name sent by the client.
+    fn create_target_request<B: AsRef<[u8]>>(
+        &self,
+        target: RocketTarget,
+        mut headers: Vec<(String, String)>,
+        body: B,
+    ) -> LocalRequest {
+        headers.retain(|(name, _)| {
+            !target
+                .headers
+                .iter()
+                .any(|(n, _)| n.eq_ignore_ascii_case(name))
+        });
+        headers.extend(target.headers);
+        let local_req = self.create_local_request(target.method, target.uri, headers, body);
+        if let Some(original_method) = target.original_method {
+            local_req
+                .inner()
+                .local_cache(|| Some(OriginalMethod(original_method)));
+        }
+        local_req
+    }
+
+    fn create_local_request<B: AsRef<[u8]>>(
+        &self,
+        method: Method,
+        uri: String,
+        headers: Vec<(String, String)>,
+        body: B,
+    ) -> LocalRequest {
+        // Rocket only sets the URI of a `LocalRequest` when it is dispatched, so the expected route
+        // is found from the URI here.
+        let path = uri.split('?').next().unwrap_or_default();
+        let expected_route = route::expected_route(self.client().rocket().routes(), method, path)
+            .map(MatchedRoute::new);
+        let mut local_req = self.client().req(method, uri);
+        local_req
+            .inner()
+            .local_cache(|| ExpectedRoute(expected_route));
+        for (name, value) in headers {
+            local_req.add_header(Header::new(name, value));
+        }
+        local_req.set_body(body);
+        local_req
+    }
+
+    /// Dispatches a request to Rocket and converts the response into a Lambda response.
+    ///
+    /// Rocket handles `HEAD` requests with the matching `GET` route when there is no `HEAD` route,
+    /// but only empties the response body rather than removing it. For `HEAD` requests, the body
+    /// is dropped entirely, and the `Content-Length` that the `GET` response would have had is kept.
+    fn dispatch(&self, mut local_req: LocalRequest) -> Result<Response<Body>, RocketLambError> {
+        if let Some(deadline) = Deadline::from_context(&self.context) {
+            local_req.inner().local_cache(|| Some(deadline));
+        }
+        let ExpectedRoute(expected_route) = local_req.inner().local_cache(|| ExpectedRoute(None));
+        *self.current_route.lock().unwrap_or_else(|e| e.into_inner()) = expected_route.clone();
+        let trace_header = local_req.inner().headers().get_one("X-Amzn-Trace-Id");
+        if let Some(trace) = TraceContext::find(self.xray_trace_id(), trace_header) {
+            local_req.inner().local_cache(|| Some(trace));
+        }
+        let is_head = local_req.inner().method() == Method::Head;
+        let mut local_res = local_req.mut_dispatch();
+
+        let content_length = match local_res.body() {
+            Some(rocket::response::Body::Sized(_, size)) if is_head => Some(size),
+            _ => None,
+        };
+        let mut res = self.create_lambda_response(&mut local_res)?;
+        if is_head {
+            *res.body_mut() = Body::Empty;
+        }
+        if let Some(size) = content_length {
+            res.headers_mut()
+                .entry(http::header::CONTENT_LENGTH)
+                .map_err(|e| invalid_response!("{}", e))?
+                .or_insert_with(|| size.into());
+        }
+
+        if let Some(route) = local_req.inner().route() {
+            res.extensions_mut().insert(MatchedRoute::new(route));
+        }
+
+        for hook in &self.config.after_response_hooks {
+            hook(&local_res, &mut res, &self.context);
+        }
+        Ok(res)
     }
 
     fn create_lambda_response(
         &self,
-        mut local_res: LocalResponse,
+        local_res: &mut LocalResponse,
     ) -> Result<Response<Body>, RocketLambError> {
         let mut builder = Response::builder();
         builder.status(local_res.status().code);
@@ -124,25 +883,96 @@ impl RocketHandler {
     }
 
     fn get_path_and_query(&self, req: &Request) -> String {
-        let mut uri = match self.config.base_path_behaviour {
+        let path = match self.config.base_path_behaviour {
             BasePathBehaviour::Include | BasePathBehaviour::RemountAndInclude => req.full_path(),
-            BasePathBehaviour::Exclude => req.api_path().to_owned(),
+            BasePathBehaviour::Exclude => req.api_path(),
         };
-        let query = req.query_string_parameters();
+        self.with_normalized_query(req, path)
+    }
 
-        let mut separator = '?';
-        for (key, _) in query.iter() {
-            for value in query.get_all(key).unwrap() {
-                uri.push_str(&format!(
-                    "{}{}={}",
-                    separator,
-                    Uri::percent_encode(key),
-                    Uri::percent_encode(value)
-                ));
-                separator = '&';
+    /// Like `get_path_and_query`, but returns `None` instead of panicking if the base path cannot
+    /// be determined from the request's resource path.
+    fn try_get_path_and_query(&self, req: &Request) -> Option<String> {
+        let path = match self.config.base_path_behaviour {
+            BasePathBehaviour::Include | BasePathBehaviour::RemountAndInclude => {
+                req.try_full_path()?
             }
+            BasePathBehaviour::Exclude => req.try_api_path()?,
+        };
+        Some(self.with_normalized_query(req, path))
+    }
+
+    fn with_normalized_query(&self, req: &Request, path: String) -> String {
+        let path = self.config.path_normalization.apply(&path);
+        with_query(path, &self.get_query(req))
+    }
+
+    fn get_query(&self, req: &Request) -> String {
+        match (req.query_string(), &self.config.query_reconstruction) {
+            (Some(QueryString(query)), _) => query.clone(),
+            (None, QueryReconstruction::Ordered) => ordered_query(req),
+            (None, QueryReconstruction::Unordered) => unordered_query(req),
+        }
+    }
+}
+
+fn timeout_response(status: u16) -> Result<Response<Body>, RocketLambError> {
+    Response::builder()
+        .status(status)
+        .body(Body::Empty)
+        .map_err(|e| invalid_response!("{}", e))
+}
+
+fn with_query(mut uri: String, query: &str) -> String {
+    if !query.is_empty() {
+        uri.push('?');
+        uri.push_str(query);
+    }
+    uri
+}
+
+/// Rebuilds the query string with keys sorted by name, as `lambda_http` parses the parameters into
+/// a `HashMap`. The values of each key keep the order they were sent in, and keys with an empty
+/// value are written without `=`, e.g. `?flag`.
+fn ordered_query(req: &Request) -> String {
+    let params = req.query_string_parameters();
+    let encode = query_encoder(req);
+    let mut keys: Vec<&str> = params.iter().map(|(key, _)| key).collect();
+    keys.sort_unstable();
+
+    let mut query = String::new();
+    for key in keys {
+        for value in params.get_all(key).unwrap() {
+            let value = Some(value).filter(|v| !v.is_empty()).map(&encode);
+            push_query_pair(&mut query, &encode(key), value.as_ref().map(String::as_str));
+        }
+    }
+    query
+}
+
+fn unordered_query(req: &Request) -> String {
+    let params = req.query_string_parameters();
+    let encode = query_encoder(req);
+
+    let mut query = String::new();
+    for (key, _) in params.iter() {
+        for value in params.get_all(key).unwrap() {
+            push_query_pair(&mut query, &encode(key), Some(&encode(value)));
+        }
+    }
+    query
+}
+
+/// ALB passes query strings through exactly as the client sent them, so they are already
+/// percent-encoded, whereas API Gateway decodes them.
+fn query_encoder(req: &Request) -> impl Fn(&str) -> String {
+    let is_alb = req.request_context().is_alb();
+    move |s: &str| {
+        if is_alb {
+            s.to_owned()
+        } else {
+            encode_query_component(s)
         }
-        uri
     }
 }
 
@@ -162,3 +992,47 @@ fn to_rocket_method(method: &http::Method) -> Result<rocket::http::Method, Rocke
         _ => return Err(invalid_request!("unknown method '{}'", method)),
     })
 }
+
+/// The method, URI and additional headers that a request is dispatched to Rocket with.
+struct RocketTarget {
+    method: Method,
+    uri: String,
+    headers: Vec<(String, String)>,
+    /// The method of the request, if Rocket does not support it and it was replaced.
+    original_method: Option<String>,
+}
+
+/// Details of a request that are recorded in access logs, metrics and traces once the request
+/// has been processed.
+struct RequestTelemetry {
+    start: Instant,
+    start_time: SystemTime,
+    cold_start: bool,
+    method: String,
+    url: String,
+    log_entry: Option<AccessLogEntry>,
+    stage: String,
+    trace: Option<TraceContext>,
+    span: Option<(SpanContext, Option<String>)>,
+}
+
+fn attribute<V: Into<AttributeValue>>(key: &str, value: V) -> (String, AttributeValue) {
+    (key.to_owned(), value.into())
+}
+
+/// Creates a `Context` for invocations outside of Lambda, e.g. from the command line or when replaying
+/// recorded events, with a deadline `timeout` from now.
+pub(crate) fn local_context(request_id: String, timeout: Duration) -> Context {
+    let deadline = SystemTime::now() + timeout;
+    Context {
+        aws_request_id: request_id,
+        function_name: "rocket-lamb-local".to_owned(),
+        function_version: "$LATEST".to_owned(),
+        memory_limit_in_mb: 128,
+        deadline: deadline
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default(),
+        ..Context::default()
+    }
+}
@@ -0,0 +1,69 @@
+use serde_json::{json, Value};
+
+pub(crate) fn is_http_api_v2_event(event: &Value) -> bool {
+    event["version"] == "2.0" && event["requestContext"]["http"]["method"].is_string()
+}
+
+/// Converts an HTTP API (payload format version 2.0) event into the shape of a version 1.0
+/// event, so that it can be parsed by `lambda_http`.
+pub(crate) fn convert_to_v1(event: &mut Value) {
+    let context = event["requestContext"].take();
+    let method = context["http"]["method"].clone();
+    let stage = context["stage"].as_str().unwrap_or("$default").to_owned();
+    let domain_name = context["domainName"].as_str().unwrap_or_default();
+
+    // Like the version 1.0 `path`, the path should not include the stage when using the default
+    // API Gateway URL. Unlike version 1.0, the raw path of a named stage includes it.
+    let raw_path = event["rawPath"].as_str().unwrap_or("/").to_owned();
+    let stage_prefix = format!("/{}", stage);
+    let is_default_url =
+        domain_name.ends_with(".amazonaws.com") && domain_name.contains(".execute-api.");
+    let has_stage_prefix =
+        raw_path == stage_prefix || raw_path.starts_with(&format!("{}/", stage_prefix));
+    let path = if is_default_url && has_stage_prefix {
+        raw_path[stage_prefix.len()..].to_owned()
+    } else {
+        raw_path
+    };
+    let path = if path.is_empty() {
+        "/".to_owned()
+    } else {
+        path
+    };
+
+    let resource_path = match context["routeKey"].as_str() {
+        Some(route_key) if route_key != "$default" => route_key
+            .splitn(2, ' ')
+            .last()
+            .unwrap_or(route_key)
+            .to_owned(),
+        _ => path.clone(),
+    };
+
+    let mut headers = event["headers"].as_object().cloned().unwrap_or_default();
+    if let Some(cookies) = event["cookies"].as_array() {
+        let cookies: Vec<&str> = cookies.iter().filter_map(Value::as_str).collect();
+        headers.insert("cookie".to_owned(), Value::String(cookies.join("; ")));
+    }
+
+    event["httpMethod"] = method.clone();
+    event["path"] = Value::String(path);
+    event["headers"] = Value::Object(headers);
+    if !event["queryStringParameters"].is_object() {
+        event["queryStringParameters"] = json!({});
+    }
+    event["requestContext"] = json!({
+        "accountId": context["accountId"],
+        "resourceId": "",
+        "stage": stage,
+        "requestId": context["requestId"],
+        "resourcePath": resource_path,
+        "httpMethod": method,
+        "apiId": context["apiId"],
+        "authorizer": context["authorizer"].as_object().cloned().unwrap_or_default(),
+        "identity": {
+            "sourceIp": context["http"]["sourceIp"],
+            "userAgent": context["http"]["userAgent"],
+        },
+    });
+}
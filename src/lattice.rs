@@ -0,0 +1,139 @@
+use crate::encoding::{encode_query_component, normalize_path, push_query_pair};
+use crate::error::RocketLambError;
+use lambda_http::{Body, Response};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Deserialize)]
+pub(crate) struct LatticeEvent {
+    method: String,
+    raw_path: String,
+    #[serde(default)]
+    headers: Option<HashMap<String, MultiValue>>,
+    #[serde(default)]
+    query_string_parameters: Option<BTreeMap<String, MultiValue>>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    is_base64_encoded: bool,
+}
+
+/// Lattice may send header and query string values either as a single string or as a list.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MultiValue {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl MultiValue {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            MultiValue::Single(v) => vec![v],
+            MultiValue::Multiple(v) => v,
+        }
+    }
+}
+
+/// A VPC Lattice request converted into the parts needed to build a Rocket request.
+pub(crate) struct LatticeRequest {
+    pub(crate) method: http::Method,
+    pub(crate) uri: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl LatticeEvent {
+    pub(crate) fn into_request(self) -> Result<LatticeRequest, RocketLambError> {
+        let method = http::Method::from_bytes(self.method.as_bytes())
+            .map_err(|_| invalid_request!("invalid method '{}'", self.method))?;
+
+        let mut uri = match self.raw_path.find('?') {
+            Some(index) => {
+                let (path, query) = self.raw_path.split_at(index);
+                format!("{}{}", normalize_path(path), query)
+            }
+            None => normalize_path(&self.raw_path),
+        };
+        if !uri.contains('?') {
+            // The query is rebuilt with keys in sorted order, and each key's values in the order
+            // they were sent, so that the same request always has the same URI.
+            let mut query = String::new();
+            for (key, values) in self.query_string_parameters.unwrap_or_default() {
+                for value in values.into_vec() {
+                    let value = Some(value)
+                        .filter(|v| !v.is_empty())
+                        .map(|v| encode_query_component(&v));
+                    push_query_pair(
+                        &mut query,
+                        &encode_query_component(&key),
+                        value.as_ref().map(String::as_str),
+                    );
+                }
+            }
+            if !query.is_empty() {
+                uri.push('?');
+                uri.push_str(&query);
+            }
+        }
+
+        let headers = self
+            .headers
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|(name, values)| {
+                values
+                    .into_vec()
+                    .into_iter()
+                    .map(move |value| (name.clone(), value))
+            })
+            .collect();
+
+        let body = match self.body {
+            Some(b) if self.is_base64_encoded => base64::decode(&b)
+                .map_err(|e| invalid_request!("invalid base-64 request body: {}", e))?,
+            Some(b) => b.into_bytes(),
+            None => Vec::new(),
+        };
+
+        Ok(LatticeRequest {
+            method,
+            uri,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Serializes a response into the format expected by VPC Lattice.
+pub(crate) fn to_json(res: &Response<Body>) -> Value {
+    let mut headers = Map::new();
+    for name in res.headers().keys() {
+        let values: Vec<_> = res
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+            .collect();
+        headers.insert(name.to_string(), Value::String(values.join(", ")));
+    }
+
+    let (body, is_base64_encoded) = match res.body() {
+        Body::Empty => (String::new(), false),
+        Body::Text(s) => (s.clone(), false),
+        Body::Binary(b) => (base64::encode(b), true),
+    };
+
+    json!({
+        "statusCode": res.status().as_u16(),
+        "statusDescription": format!(
+            "{} {}",
+            res.status().as_u16(),
+            res.status().canonical_reason().unwrap_or_default()
+        ),
+        "headers": headers,
+        "body": body,
+        "isBase64Encoded": is_base64_encoded,
+    })
+}
@@ -3,7 +3,26 @@ A crate to allow running a [Rocket](https://rocket.rs/) webserver as an AWS Lamb
 
 The function takes a request from an AWS API Gateway Proxy and converts it into a `LocalRequest` to pass to Rocket. Then it will convert the response from Rocket into the response body that API Gateway understands.
 
-This *should* also work with requests from an AWS Application Load Balancer, but this has not been tested.
+Requests from an AWS Application Load Balancer are also supported. When handling raw JSON events (e.g. when using
+[launch_raw()](crate::RocketHandlerBuilder::launch_raw)), responses will use multi-value headers if the target group
+has them enabled. Health check requests can be identified with the [AlbHealthCheck](crate::AlbHealthCheck) request guard.
+
+When handling raw JSON events (e.g. when using [launch_raw()](crate::RocketHandlerBuilder::launch_raw)), events from these sources are also supported:
+- API Gateway HTTP APIs using payload format version 2.0
+- API Gateway WebSocket APIs - see [WebSocketConnection](crate::WebSocketConnection)
+- VPC Lattice services
+- CloudFront viewer requests and origin requests for Lambda@Edge - see [edge_continue_status](crate::RocketHandlerBuilder::edge_continue_status)
+- Direct invocations, e.g. from Step Functions - see [direct_invocation_route](crate::RocketHandlerBuilder::direct_invocation_route)
+
+With the `testing` feature enabled, the `testing` module provides builders for these events and helpers for
+asserting on responses, so that handlers can be tested without deploying them.
+
+## Request paths
+
+The path processed by Rocket is always percent-encoded, regardless of whether the integration passed it encoded or
+decoded. Existing percent-encoded sequences are preserved, so an encoded slash (`%2F`) stays within a single segment
+and is decoded as part of a route's parameter. Characters that are not valid in a URI path, such as spaces or
+non-ASCII characters, are percent-encoded.
 
 ## Usage
 
@@ -37,14 +56,53 @@ extern crate failure;
 #[macro_use]
 mod error;
 
+mod access_log;
+mod alb;
 mod builder;
+mod cli;
 mod config;
+mod config_source;
+mod deadline;
+mod edge;
+mod encoding;
+mod event;
 mod handler;
+mod http_api;
+mod lattice;
+mod method;
+mod metrics;
+mod openapi;
+mod otel;
+mod params;
+mod recording;
 mod request_ext;
+mod response;
+mod route;
+mod sam;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod websocket;
+mod xray;
 
+pub use access_log::{AccessLogEntry, AccessLogFormat, AccessLogSink, StdoutAccessLog};
+pub use alb::AlbHealthCheck;
 pub use builder::*;
 pub use config::*;
+pub use config_source::ConfigError;
+pub use deadline::Deadline;
 pub use handler::*;
+pub use method::OriginalMethod;
+pub use otel::{
+    AttributeValue, InMemorySpanExporter, OtlpHttpExporter, Span, SpanContext, SpanExporter,
+};
+pub use params::{PathParameters, StageVariables};
+pub use recording::{
+    load_recordings, DirectoryRecordingSink, InMemoryRecordingSink, RecordedInvocation,
+    RecordingSink, ReplayResult, StdoutRecordingSink,
+};
+pub use sam::{SamEventSource, SamRoutes};
+pub use websocket::{ConnectionClient, InMemoryConnectionClient, WebSocketConnection};
+pub use xray::TraceContext;
 
 /// Extensions for `rocket::Rocket` to make it easier to create Lambda handlers.
 pub trait RocketExt {
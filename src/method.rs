@@ -0,0 +1,85 @@
+use rocket::http::Method;
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request, Route};
+
+/// The methods that may be listed in an `Allow` header, in the order they are listed.
+const METHODS: [Method; 9] = [
+    Method::Get,
+    Method::Head,
+    Method::Post,
+    Method::Put,
+    Method::Delete,
+    Method::Patch,
+    Method::Options,
+    Method::Trace,
+    Method::Connect,
+];
+
+/// Request guard for the original HTTP method of a request whose method Rocket does not support,
+/// and which was dispatched with another method because of an
+/// [ExtensionMethodBehaviour](crate::ExtensionMethodBehaviour).
+///
+/// This guard forwards if the request's method was not changed.
+///
+/// # Example
+///
+/// ```rust
+/// #![feature(proc_macro_hygiene, decl_macro)]
+/// # #[macro_use] extern crate rocket;
+/// use rocket_lamb::OriginalMethod;
+///
+/// #[post("/fallback")]
+/// fn fallback(method: OriginalMethod) -> String {
+///     format!("{} is not supported", method.0)
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OriginalMethod(pub String);
+
+impl<'a, 'r> FromRequest<'a, 'r> for OriginalMethod {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match request.local_cache(|| None::<OriginalMethod>) {
+            Some(method) => Outcome::Success(method.clone()),
+            None => Outcome::Forward(()),
+        }
+    }
+}
+
+/// Gets the methods of the given routes whose path matches `path`, for use in an `Allow` header.
+/// `HEAD` is included for any path that allows `GET`, as Rocket handles `HEAD` requests automatically.
+pub(crate) fn allowed_methods<'a>(
+    routes: impl Iterator<Item = &'a Route>,
+    path: &str,
+) -> Vec<Method> {
+    let mut allowed: Vec<Method> = routes
+        .filter(|route| route_matches_path(route, path))
+        .map(|route| route.method)
+        .collect();
+    if allowed.contains(&Method::Get) {
+        allowed.push(Method::Head);
+    }
+    METHODS
+        .iter()
+        .copied()
+        .filter(|method| allowed.contains(method))
+        .collect()
+}
+
+/// Checks whether a route's path matches `path`, ignoring the route's query and any route guards.
+pub(crate) fn route_matches_path(route: &Route, path: &str) -> bool {
+    let mut path_segments = path.split('/').filter(|s| !s.is_empty());
+    for route_segment in route.uri.path().split('/').filter(|s| !s.is_empty()) {
+        if route_segment.starts_with('<') && route_segment.ends_with("..>") {
+            return true;
+        }
+        match path_segments.next() {
+            Some(_) if route_segment.starts_with('<') => {}
+            Some(segment) if segment == route_segment => {}
+            _ => return false,
+        }
+    }
+    path_segments.next().is_none()
+}
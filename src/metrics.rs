@@ -0,0 +1,91 @@
+use crate::route::MatchedRoute;
+use lambda_http::{Body, Response};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Emits CloudWatch Embedded Metric Format documents, one per request.
+pub(crate) struct EmfMetrics {
+    namespace: String,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EmfMetrics {
+    pub(crate) fn new(namespace: &str, writer: Box<dyn Write + Send>) -> EmfMetrics {
+        EmfMetrics {
+            namespace: namespace.to_owned(),
+            writer: Mutex::new(writer),
+        }
+    }
+
+    pub(crate) fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub(crate) fn set_namespace(&mut self, namespace: &str) {
+        self.namespace = namespace.to_owned();
+    }
+
+    /// Writes the metrics for a response, dimensioned by stage and the Rocket route that handled
+    /// the request. Requests that did not match a route (e.g. those handled by a catcher) are
+    /// grouped under the route `unmatched`.
+    pub(crate) fn record(
+        &self,
+        stage: &str,
+        res: &Response<Body>,
+        latency: Duration,
+        cold_start: bool,
+    ) {
+        let route = res
+            .extensions()
+            .get::<MatchedRoute>()
+            .map(MatchedRoute::id)
+            .unwrap_or_else(|| "unmatched".to_owned());
+        let route_name = res.extensions().get::<MatchedRoute>().and_then(|r| r.name);
+        let status_class = res.status().as_u16() / 100;
+        let count = |condition: bool| if condition { 1 } else { 0 };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+
+        let document = json!({
+            "_aws": {
+                "Timestamp": timestamp as u64,
+                "CloudWatchMetrics": [{
+                    "Namespace": self.namespace,
+                    "Dimensions": [["Stage", "Route"]],
+                    "Metrics": [
+                        { "Name": "Latency", "Unit": "Milliseconds" },
+                        { "Name": "2xx", "Unit": "Count" },
+                        { "Name": "3xx", "Unit": "Count" },
+                        { "Name": "4xx", "Unit": "Count" },
+                        { "Name": "5xx", "Unit": "Count" },
+                        { "Name": "ResponseBytes", "Unit": "Bytes" },
+                        { "Name": "Base64Responses", "Unit": "Count" },
+                        { "Name": "ColdStart", "Unit": "Count" },
+                    ],
+                }],
+            },
+            "Stage": if stage.is_empty() { "-" } else { stage },
+            "Route": route,
+            "RouteName": route_name,
+            "Latency": latency.as_secs_f64() * 1000.0,
+            "2xx": count(status_class == 2),
+            "3xx": count(status_class == 3),
+            "4xx": count(status_class == 4),
+            "5xx": count(status_class == 5),
+            "ResponseBytes": res.body().len(),
+            "Base64Responses": count(matches!(res.body(), Body::Binary(_))),
+            "ColdStart": count(cold_start),
+        });
+        write_line(&self.writer, &document);
+    }
+}
+
+fn write_line(writer: &Mutex<Box<dyn Write + Send>>, document: &Value) {
+    let mut writer = writer.lock().unwrap();
+    // Metrics are best-effort, so a failure to write them must not fail the request.
+    let _ = writeln!(writer, "{}", document).and_then(|_| writer.flush());
+}
@@ -0,0 +1,201 @@
+use crate::config::{Config, ResponseType};
+use rocket::Route;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Converts the path of a Rocket route into an API Gateway resource path, along with the names
+/// of its path parameters, e.g. `/files/<dir>/<path..>` becomes `/files/{dir}/{path+}`.
+///
+/// API Gateway only allows one path parameter under each resource, so `parameter_names` records the
+/// name used under each resource path, and later routes reuse it, e.g. `/items/<name>/edit` becomes
+/// `/items/{id}/edit` if `/items/<id>` was converted first.
+fn resource_path(
+    route: &Route,
+    parameter_names: &mut HashMap<String, String>,
+) -> (String, Vec<String>) {
+    let mut path = String::new();
+    let mut parameters = Vec::new();
+    for segment in route.uri.path().split('/').filter(|s| !s.is_empty()) {
+        let segment = if segment.starts_with('<') && segment.ends_with('>') {
+            let name = &segment[1..segment.len() - 1];
+            let greedy = name.ends_with("..");
+            let name = parameter_names
+                .entry(path.clone())
+                .or_insert_with(|| name.trim_end_matches("..").to_owned())
+                .clone();
+            let segment = if greedy {
+                format!("{{{}+}}", name)
+            } else {
+                format!("{{{}}}", name)
+            };
+            parameters.push(name);
+            segment
+        } else {
+            segment.to_owned()
+        };
+        path.push('/');
+        path.push_str(&segment);
+    }
+    if path.is_empty() {
+        path.push('/');
+    }
+    (path, parameters)
+}
+
+/// The content types that API Gateway must treat as binary, from the configured response types.
+pub(crate) fn binary_media_types(config: &Config) -> Vec<String> {
+    if config.default_response_type == ResponseType::Binary {
+        return vec!["*/*".to_owned()];
+    }
+    let mut types: Vec<String> = config
+        .response_types
+        .iter()
+        .filter(|(_, response_type)| **response_type == ResponseType::Binary)
+        .map(|(content_type, _)| content_type.clone())
+        .collect();
+    types.sort();
+    types
+}
+
+/// A route as it is exposed through API Gateway.
+pub(crate) struct ApiRoute<'a> {
+    pub(crate) path: String,
+    pub(crate) parameters: Vec<String>,
+    pub(crate) route: &'a Route,
+}
+
+impl<'a> ApiRoute<'a> {
+    /// A `<path..>` segment also matches no segments at all, but API Gateway's `{path+}` does not,
+    /// so a route ending with one is also exposed on the parent resource, e.g. `/files/{path+}`
+    /// is also exposed as `/files`.
+    fn greedy_parent(&self) -> Option<ApiRoute<'a>> {
+        if !self.path.ends_with("+}") {
+            return None;
+        }
+        let parent = &self.path[..self.path.rfind('/').unwrap_or_default()];
+        Some(ApiRoute {
+            path: if parent.is_empty() { "/" } else { parent }.to_owned(),
+            parameters: self.parameters[..self.parameters.len() - 1].to_vec(),
+            route: self.route,
+        })
+    }
+}
+
+/// Lists the routes as they are exposed through API Gateway, sorted by path and method.
+///
+/// Rocket tries routes with the same method and path in order of rank, but API Gateway only allows
+/// one operation for each, which is forwarded to Rocket regardless. So only the first is listed.
+pub(crate) fn api_routes<'a>(routes: impl Iterator<Item = &'a Route>) -> Vec<ApiRoute<'a>> {
+    // Routes are converted in a fixed order, so that the same parameter names are always chosen.
+    let mut routes: Vec<&Route> = routes.collect();
+    routes.sort_by(|a, b| {
+        (a.uri.path(), a.method.as_str(), a.rank).cmp(&(b.uri.path(), b.method.as_str(), b.rank))
+    });
+    let mut parameter_names = HashMap::new();
+    let mut api_routes: Vec<ApiRoute> = routes
+        .into_iter()
+        .map(|route| {
+            let (path, parameters) = resource_path(route, &mut parameter_names);
+            ApiRoute {
+                path,
+                parameters,
+                route,
+            }
+        })
+        .collect();
+    sort_and_dedup(&mut api_routes);
+
+    let parents: Vec<ApiRoute> = api_routes
+        .iter()
+        .filter_map(ApiRoute::greedy_parent)
+        .filter(|parent| {
+            !api_routes
+                .iter()
+                .any(|r| r.path == parent.path && r.route.method == parent.route.method)
+        })
+        .collect();
+    api_routes.extend(parents);
+    sort_and_dedup(&mut api_routes);
+    api_routes
+}
+
+fn sort_and_dedup(api_routes: &mut Vec<ApiRoute>) {
+    api_routes.sort_by(|a, b| {
+        (&a.path, a.route.method.as_str(), a.route.rank).cmp(&(
+            &b.path,
+            b.route.method.as_str(),
+            b.route.rank,
+        ))
+    });
+    api_routes.dedup_by(|b, a| a.path == b.path && a.route.method == b.route.method);
+}
+
+/// Generates an OpenAPI 3 document with an API Gateway Lambda proxy integration for each route.
+pub(crate) fn document<'a>(
+    routes: impl Iterator<Item = &'a Route>,
+    config: &Config,
+    title: &str,
+    version: &str,
+    integration_uri: &str,
+) -> Value {
+    let mut paths = Map::new();
+    let mut operation_ids = HashMap::new();
+    for ApiRoute {
+        path,
+        parameters,
+        route,
+    } in api_routes(routes)
+    {
+        let method = route.method.as_str().to_lowercase();
+        let operations = paths.entry(path).or_insert_with(|| json!({}));
+
+        let parameters: Vec<Value> = parameters
+            .iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                })
+            })
+            .collect();
+        let mut operation = json!({
+            "responses": {
+                "default": { "description": "The response from the Lambda function" }
+            },
+            "x-amazon-apigateway-integration": {
+                "type": "aws_proxy",
+                "httpMethod": "POST",
+                "uri": integration_uri,
+                "passthroughBehavior": "when_no_match",
+            },
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if let Some(name) = route.name {
+            let count = operation_ids.entry(name).or_insert(0);
+            *count += 1;
+            operation["operationId"] = match *count {
+                1 => Value::String(name.to_owned()),
+                n => Value::String(format!("{}_{}", name, n)),
+            };
+        }
+        operations[method.as_str()] = operation;
+    }
+
+    let mut document = json!({
+        "openapi": "3.0.1",
+        "info": {
+            "title": title,
+            "version": version,
+        },
+        "paths": paths,
+    });
+    let media_types = binary_media_types(config);
+    if !media_types.is_empty() {
+        document["x-amazon-apigateway-binary-media-types"] = json!(media_types);
+    }
+    document
+}
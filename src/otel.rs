@@ -0,0 +1,438 @@
+use crate::error::RocketLambError;
+use crate::xray::{new_id, TraceContext};
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request};
+use serde_json::{json, Value};
+use std::env;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The OTLP/HTTP traces endpoint when no endpoint is configured in the environment.
+const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4318/v1/traces";
+
+/// How long to wait for the collector in total by default, as spans are exported before the
+/// invocation completes.
+const DEFAULT_OTLP_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// The W3C trace context of an OpenTelemetry span.
+///
+/// This is also a request guard for the context of the server span created for the current
+/// request when OpenTelemetry tracing is enabled with
+/// [opentelemetry](crate::RocketHandlerBuilder::opentelemetry), so that routes can create
+/// child spans or propagate the context to downstream services. The guard forwards if tracing
+/// is not enabled.
+///
+/// # Example
+///
+/// ```rust
+/// #![feature(proc_macro_hygiene, decl_macro)]
+/// # #[macro_use] extern crate rocket;
+/// use rocket_lamb::SpanContext;
+///
+/// #[get("/downstream")]
+/// fn downstream(span: SpanContext) -> String {
+///     // Send this as the `traceparent` header of requests to downstream services.
+///     span.traceparent()
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpanContext {
+    /// The 16-byte trace ID, formatted as 32 lowercase hexadecimal digits.
+    pub trace_id: String,
+    /// The 8-byte span ID, formatted as 16 lowercase hexadecimal digits.
+    pub span_id: String,
+    /// Whether the trace is sampled.
+    pub sampled: bool,
+}
+
+impl SpanContext {
+    /// Parses a W3C `traceparent` header, e.g.
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::SpanContext;
+    ///
+    /// let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    /// let context = SpanContext::from_traceparent(header).unwrap();
+    /// assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    /// assert_eq!(context.span_id, "00f067aa0ba902b7");
+    /// assert!(context.sampled);
+    /// assert_eq!(context.traceparent(), header);
+    /// ```
+    pub fn from_traceparent(header: &str) -> Option<SpanContext> {
+        let parts: Vec<&str> = header.trim().split('-').collect();
+        match parts.as_slice() {
+            [version, trace_id, span_id, flags]
+                if version.len() == 2
+                    && *version != "ff"
+                    && is_hex_id(trace_id, 32)
+                    && is_hex_id(span_id, 16)
+                    && flags.len() == 2 =>
+            {
+                let flags = u8::from_str_radix(flags, 16).ok()?;
+                Some(SpanContext {
+                    trace_id: (*trace_id).to_owned(),
+                    span_id: (*span_id).to_owned(),
+                    sampled: flags & 1 == 1,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts an X-Ray trace context into a span context, e.g. the trace ID
+    /// `1-5759e988-bd862e3fe1be46a994272793` becomes `5759e988bd862e3fe1be46a994272793`.
+    /// Returns `None` if the X-Ray trace has no parent segment.
+    pub fn from_xray(trace: &TraceContext) -> Option<SpanContext> {
+        let parts: Vec<&str> = trace.root.split('-').collect();
+        let trace_id = match parts.as_slice() {
+            ["1", time, random] => format!("{}{}", time, random),
+            _ => return None,
+        };
+        let span_id = trace.parent.as_ref()?;
+        if !is_hex_id(&trace_id, 32) || !is_hex_id(span_id, 16) {
+            return None;
+        }
+        Some(SpanContext {
+            trace_id,
+            span_id: span_id.clone(),
+            sampled: trace.sampled.unwrap_or(true),
+        })
+    }
+
+    /// Formats the context as a W3C `traceparent` header.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{}",
+            self.trace_id,
+            self.span_id,
+            if self.sampled { "01" } else { "00" }
+        )
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for SpanContext {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match request.local_cache(|| None::<SpanContext>) {
+            Some(context) => Outcome::Success(context.clone()),
+            None => Outcome::Forward(()),
+        }
+    }
+}
+
+/// The value of a span attribute.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributeValue {
+    /// A string value.
+    String(String),
+    /// An integer value.
+    Int(i64),
+    /// A boolean value.
+    Bool(bool),
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> AttributeValue {
+        AttributeValue::String(value.to_owned())
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> AttributeValue {
+        AttributeValue::Int(value)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> AttributeValue {
+        AttributeValue::Bool(value)
+    }
+}
+
+/// A completed server span for a request processed by a [RocketHandler](crate::RocketHandler).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    /// The name of the span, e.g. `GET /items/<id>`.
+    pub name: String,
+    /// The context of this span.
+    pub context: SpanContext,
+    /// The span ID of the parent span, if the request was part of an existing trace.
+    pub parent_span_id: Option<String>,
+    /// The time at which the span started.
+    pub start_time: SystemTime,
+    /// The time at which the span ended.
+    pub end_time: SystemTime,
+    /// The attributes of the span, following the OpenTelemetry semantic conventions for HTTP
+    /// servers and FaaS.
+    pub attributes: Vec<(String, AttributeValue)>,
+    /// Whether the span represents an error, i.e. the response had a `5xx` status.
+    pub is_error: bool,
+}
+
+impl Span {
+    /// Gets the value of the attribute with the given key.
+    pub fn attribute(&self, key: &str) -> Option<&AttributeValue> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+/// A destination for completed spans.
+pub trait SpanExporter: Send + Sync {
+    /// Exports a completed span.
+    fn export(&self, span: &Span);
+}
+
+/// A `SpanExporter` that records spans in memory, for use in tests.
+#[derive(Default, Debug)]
+pub struct InMemorySpanExporter {
+    spans: Mutex<Vec<Span>>,
+}
+
+impl InMemorySpanExporter {
+    /// Gets all spans that have been exported, in the order they were exported.
+    pub fn spans(&self) -> Vec<Span> {
+        self.spans.lock().unwrap().clone()
+    }
+}
+
+impl SpanExporter for InMemorySpanExporter {
+    fn export(&self, span: &Span) {
+        self.spans.lock().unwrap().push(span.clone());
+    }
+}
+
+impl<E: SpanExporter + ?Sized> SpanExporter for std::sync::Arc<E> {
+    fn export(&self, span: &Span) {
+        (**self).export(span)
+    }
+}
+
+/// A `SpanExporter` that sends spans to an OpenTelemetry collector using OTLP over HTTP with
+/// JSON encoding, e.g. to the collector provided by the AWS Distro for OpenTelemetry Lambda layer.
+///
+/// Spans are sent synchronously when each request completes, as Lambda may freeze the execution
+/// environment once the invocation completes. Only `http` endpoints are supported, so the
+/// collector should run alongside the function. Exporting a span takes at most the exporter's
+/// [timeout](OtlpHttpExporter::timeout), 200ms by default, and failures, including responses
+/// without a `2xx` status, are logged without failing the request.
+///
+/// # Example
+///
+/// ```rust
+/// use rocket_lamb::{OtlpHttpExporter, RocketExt};
+///
+/// let builder = rocket::ignite()
+///     .lambda()
+///     .opentelemetry(OtlpHttpExporter::from_env());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OtlpHttpExporter {
+    endpoint: String,
+    service_name: String,
+    timeout: Duration,
+}
+
+impl OtlpHttpExporter {
+    /// Creates an exporter that sends spans to the given OTLP/HTTP traces endpoint, e.g.
+    /// `http://localhost:4318/v1/traces`.
+    pub fn new(endpoint: &str) -> OtlpHttpExporter {
+        OtlpHttpExporter {
+            endpoint: endpoint.to_owned(),
+            service_name: service_name_from_env(),
+            timeout: DEFAULT_OTLP_TIMEOUT,
+        }
+    }
+
+    /// Sets the longest time that exporting a span may take, including connecting to the collector,
+    /// sending the span and reading the collector's response.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Creates an exporter using the endpoint in `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`, or
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` followed by `/v1/traces`, or `http://localhost:4318/v1/traces`.
+    ///
+    /// The service name is taken from `OTEL_SERVICE_NAME`, or the name of the Lambda function.
+    pub fn from_env() -> OtlpHttpExporter {
+        let endpoint = env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")
+            .or_else(|_| {
+                env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                    .map(|e| format!("{}/v1/traces", e.trim_end_matches('/')))
+            })
+            .unwrap_or_else(|_| DEFAULT_OTLP_ENDPOINT.to_owned());
+        OtlpHttpExporter::new(&endpoint)
+    }
+
+    fn send(&self, body: &str) -> Result<(), RocketLambError> {
+        let rest = self.endpoint.trim_start_matches("http://");
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/v1/traces"),
+        };
+        let io_error = |e: std::io::Error| export_failed!("{}: {}", self.endpoint, e);
+        let addr = authority
+            .to_socket_addrs()
+            .map_err(io_error)?
+            .next()
+            .ok_or_else(|| export_failed!("{}: could not resolve {}", self.endpoint, authority))?;
+        let deadline = Instant::now() + self.timeout;
+        let remaining = || {
+            deadline
+                .checked_duration_since(Instant::now())
+                .filter(|remaining| *remaining > Duration::from_secs(0))
+                .ok_or_else(|| export_failed!("{}: timed out", self.endpoint))
+        };
+        let mut stream = TcpStream::connect_timeout(&addr, remaining()?).map_err(io_error)?;
+        stream
+            .set_write_timeout(Some(remaining()?))
+            .map_err(io_error)?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            path,
+            authority,
+            body.len(),
+            body
+        )
+        .and_then(|_| stream.flush())
+        .map_err(io_error)?;
+        // Wait for the collector to accept the spans before the invocation completes, as Lambda
+        // may freeze the execution environment afterwards.
+        stream
+            .set_read_timeout(Some(remaining()?))
+            .map_err(io_error)?;
+        let mut status_line = String::new();
+        BufReader::new(stream.take(256))
+            .read_line(&mut status_line)
+            .map_err(io_error)?;
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok());
+        match status {
+            Some(status) if (200..300).contains(&status) => Ok(()),
+            _ => Err(export_failed!(
+                "{} responded with {:?}",
+                self.endpoint,
+                status_line.trim_end()
+            )),
+        }
+    }
+}
+
+impl SpanExporter for OtlpHttpExporter {
+    fn export(&self, span: &Span) {
+        let body = json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [
+                        otlp_attribute(
+                            "service.name",
+                            &AttributeValue::String(self.service_name.clone()),
+                        ),
+                        otlp_attribute("cloud.provider", &AttributeValue::String("aws".to_owned())),
+                    ],
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "rocket_lamb", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": [otlp_span(span)],
+                }],
+            }],
+        });
+        // Tracing is best-effort, so a failure to reach the collector must not fail the request.
+        if let Err(e) = self.send(&body.to_string()) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+fn otlp_span(span: &Span) -> Value {
+    let mut value = json!({
+        "traceId": span.context.trace_id,
+        "spanId": span.context.span_id,
+        "name": span.name,
+        // SPAN_KIND_SERVER
+        "kind": 2,
+        "startTimeUnixNano": epoch_nanos(span.start_time).to_string(),
+        "endTimeUnixNano": epoch_nanos(span.end_time).to_string(),
+        "attributes": span
+            .attributes
+            .iter()
+            .map(|(k, v)| otlp_attribute(k, v))
+            .collect::<Vec<_>>(),
+        // STATUS_CODE_ERROR or STATUS_CODE_UNSET
+        "status": { "code": if span.is_error { 2 } else { 0 } },
+    });
+    if let Some(parent) = &span.parent_span_id {
+        value["parentSpanId"] = Value::String(parent.clone());
+    }
+    value
+}
+
+fn otlp_attribute(key: &str, value: &AttributeValue) -> Value {
+    let value = match value {
+        AttributeValue::String(s) => json!({ "stringValue": s }),
+        // 64-bit integers are encoded as strings in OTLP/JSON.
+        AttributeValue::Int(i) => json!({ "intValue": i.to_string() }),
+        AttributeValue::Bool(b) => json!({ "boolValue": b }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+fn service_name_from_env() -> String {
+    env::var("OTEL_SERVICE_NAME")
+        .or_else(|_| env::var("AWS_LAMBDA_FUNCTION_NAME"))
+        .unwrap_or_else(|_| "unknown_service".to_owned())
+}
+
+/// Gets the context of the span that the server span for a request should be a child of, from
+/// the W3C `traceparent` header, or failing that, the X-Ray trace context.
+pub(crate) fn parent_context(
+    traceparent: Option<&str>,
+    xray: Option<&TraceContext>,
+) -> Option<SpanContext> {
+    traceparent
+        .and_then(SpanContext::from_traceparent)
+        .or_else(|| xray.and_then(SpanContext::from_xray))
+}
+
+/// Creates the context of a new span, continuing the trace of `parent` if there is one.
+pub(crate) fn new_span_context(parent: Option<&SpanContext>) -> SpanContext {
+    match parent {
+        Some(parent) => SpanContext {
+            trace_id: parent.trace_id.clone(),
+            span_id: new_id(),
+            sampled: parent.sampled,
+        },
+        None => SpanContext {
+            trace_id: format!("{}{}", new_id(), new_id()),
+            span_id: new_id(),
+            sampled: true,
+        },
+    }
+}
+
+fn is_hex_id(id: &str, len: usize) -> bool {
+    id.len() == len
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        && id.bytes().any(|b| b != b'0')
+}
+
+fn epoch_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
@@ -0,0 +1,98 @@
+use lambda_http::request::RequestContext;
+use lambda_http::{Request, RequestExt};
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request as RocketRequest};
+use std::collections::HashMap;
+
+/// Request guard for the path parameters that API Gateway extracted from the request path using
+/// the resource path of the API, e.g. `{proxy+}` or `{id}`.
+///
+/// This guard forwards if the request did not come from API Gateway.
+///
+/// # Example
+///
+/// ```rust
+/// #![feature(proc_macro_hygiene, decl_macro)]
+/// # #[macro_use] extern crate rocket;
+/// use rocket_lamb::PathParameters;
+///
+/// #[get("/items/<id>")]
+/// fn item(id: String, params: PathParameters) -> String {
+///     format!("{} was matched by {:?}", id, params.get("proxy"))
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PathParameters(pub HashMap<String, String>);
+
+impl PathParameters {
+    /// Gets the value of the path parameter with the given name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for PathParameters {
+    type Error = ();
+
+    fn from_request(request: &'a RocketRequest<'r>) -> request::Outcome<Self, Self::Error> {
+        match request.local_cache(|| None::<PathParameters>) {
+            Some(params) => Outcome::Success(params.clone()),
+            None => Outcome::Forward(()),
+        }
+    }
+}
+
+/// Request guard for the stage variables of the API Gateway stage that received the request.
+///
+/// This guard forwards if the request did not come from API Gateway.
+///
+/// # Example
+///
+/// ```rust
+/// #![feature(proc_macro_hygiene, decl_macro)]
+/// # #[macro_use] extern crate rocket;
+/// use rocket_lamb::StageVariables;
+///
+/// #[get("/table")]
+/// fn table(variables: StageVariables) -> String {
+///     variables.get("table").unwrap_or("dev").to_owned()
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StageVariables(pub HashMap<String, String>);
+
+impl StageVariables {
+    /// Gets the value of the stage variable with the given name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for StageVariables {
+    type Error = ();
+
+    fn from_request(request: &'a RocketRequest<'r>) -> request::Outcome<Self, Self::Error> {
+        match request.local_cache(|| None::<StageVariables>) {
+            Some(variables) => Outcome::Success(variables.clone()),
+            None => Outcome::Forward(()),
+        }
+    }
+}
+
+/// Gets the path parameters and stage variables of an API Gateway request, or `None` for
+/// Application Load Balancer requests.
+pub(crate) fn api_gateway_params(req: &Request) -> Option<(PathParameters, StageVariables)> {
+    match req.request_context() {
+        RequestContext::ApiGateway { .. } => Some((
+            PathParameters(to_map(req.path_parameters().iter())),
+            StageVariables(to_map(req.stage_variables().iter())),
+        )),
+        RequestContext::Alb { .. } => None,
+    }
+}
+
+fn to_map<'a>(iter: impl Iterator<Item = (&'a str, &'a str)>) -> HashMap<String, String> {
+    iter.map(|(k, v)| (k.to_owned(), v.to_owned())).collect()
+}
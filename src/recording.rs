@@ -0,0 +1,430 @@
+use crate::access_log::{self, REDACTED};
+use crate::config::Config;
+use crate::handler::{local_context, RocketHandler};
+use lambda_runtime::error::HandlerError;
+use serde_json::{json, Deserializer, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// An event received by a [RocketHandler](crate::RocketHandler) and the result it produced, recorded when
+/// recording is enabled with [record_events](crate::RocketHandlerBuilder::record_events).
+///
+/// Sensitive values in the event and response (e.g. headers, query parameters and bodies) have already been
+/// redacted, and are replaced with `"REDACTED"`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedInvocation {
+    /// The Lambda request ID of the invocation.
+    pub request_id: String,
+    /// The time the event was received.
+    pub time: SystemTime,
+    /// The raw JSON event.
+    pub event: Value,
+    /// The JSON response, or the message of the error returned by the handler.
+    pub result: Result<Value, String>,
+}
+
+impl RecordedInvocation {
+    /// Formats the invocation as a JSON object, with either a `response` or an `error` field.
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "requestId": self.request_id,
+            "time": self.time.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default(),
+            "event": self.event,
+        });
+        match &self.result {
+            Ok(response) => value["response"] = response.clone(),
+            Err(error) => value["error"] = Value::String(error.clone()),
+        }
+        value
+    }
+
+    /// Reads an invocation from the JSON object created by [to_json](RecordedInvocation::to_json).
+    pub fn from_json(value: &Value) -> Option<RecordedInvocation> {
+        let result = match (value.get("response"), value["error"].as_str()) {
+            (Some(response), _) => Ok(response.clone()),
+            (None, Some(error)) => Err(error.to_owned()),
+            (None, None) => return None,
+        };
+        Some(RecordedInvocation {
+            request_id: value["requestId"].as_str()?.to_owned(),
+            time: UNIX_EPOCH + Duration::from_millis(value["time"].as_u64().unwrap_or_default()),
+            event: value.get("event")?.clone(),
+            result,
+        })
+    }
+
+    /// Runs the recorded event through `handler`, and compares the result with the recorded result.
+    /// Values that were redacted when the invocation was recorded are not compared.
+    ///
+    /// The event is run with a `Context` using the recorded request ID and a deadline 30 seconds from now.
+    pub fn replay(&self, handler: &mut RocketHandler) -> ReplayResult {
+        use lambda_runtime::Handler;
+
+        let ctx = local_context(self.request_id.clone(), Duration::from_secs(30));
+        let actual = handler
+            .run(self.event.clone(), ctx)
+            .map_err(|e| e.to_string());
+
+        let mut differences = Vec::new();
+        match (&self.result, &actual) {
+            (Ok(expected), Ok(actual)) => diff("$", expected, actual, &mut differences),
+            (Err(expected), Err(actual)) if expected == actual => {}
+            (expected, actual) => differences.push(format!(
+                "$: expected {}, got {}",
+                describe_result(expected),
+                describe_result(actual)
+            )),
+        }
+        ReplayResult {
+            request_id: self.request_id.clone(),
+            expected: self.result.clone(),
+            actual,
+            differences,
+        }
+    }
+}
+
+/// The outcome of replaying a [RecordedInvocation].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayResult {
+    /// The request ID of the recorded invocation.
+    pub request_id: String,
+    /// The recorded result.
+    pub expected: Result<Value, String>,
+    /// The result of replaying the event.
+    pub actual: Result<Value, String>,
+    /// Descriptions of each difference between the results, e.g.
+    /// `$.headers.content-type: expected "text/plain", got "text/html"`.
+    pub differences: Vec<String>,
+}
+
+impl ReplayResult {
+    /// Determines whether the replayed result matched the recorded result.
+    pub fn is_match(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+fn describe_result(result: &Result<Value, String>) -> String {
+    match result {
+        Ok(response) => format!("response {}", response),
+        Err(error) => format!("error {:?}", error),
+    }
+}
+
+/// Lists the differences between two JSON values. Object keys are compared regardless of their order.
+fn diff(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected_value) in expected {
+                let key_path = format!("{}.{}", path, key);
+                match actual.get(key) {
+                    Some(actual_value) => {
+                        diff(&key_path, expected_value, actual_value, differences)
+                    }
+                    None => differences.push(format!("{}: missing", key_path)),
+                }
+            }
+            for key in actual.keys().filter(|k| !expected.contains_key(*k)) {
+                differences.push(format!("{}.{}: unexpected {}", path, key, actual[key]));
+            }
+        }
+        (Value::Array(e), Value::Array(a)) if e.len() == a.len() => {
+            for (i, (expected, actual)) in e.iter().zip(a).enumerate() {
+                diff(&format!("{}[{}]", path, i), expected, actual, differences);
+            }
+        }
+        (Value::String(expected), _) if expected == REDACTED => {}
+        _ if expected != actual => {
+            differences.push(format!("{}: expected {}, got {}", path, expected, actual))
+        }
+        _ => {}
+    }
+}
+
+/// A destination for recorded invocations.
+pub trait RecordingSink: Send + Sync {
+    /// Records an invocation.
+    fn record(&self, invocation: &RecordedInvocation);
+}
+
+/// A `RecordingSink` that writes one JSON line per invocation to stdout, which Lambda sends to CloudWatch Logs.
+///
+/// Lines copied from CloudWatch Logs into a file can be read with [load_recordings].
+///
+/// # Example
+///
+/// ```rust
+/// use rocket_lamb::{RocketExt, StdoutRecordingSink};
+///
+/// let builder = rocket::ignite()
+///     .lambda()
+///     .record_events(StdoutRecordingSink);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StdoutRecordingSink;
+
+impl RecordingSink for StdoutRecordingSink {
+    fn record(&self, invocation: &RecordedInvocation) {
+        println!("{}", invocation.to_json());
+    }
+}
+
+/// A `RecordingSink` that writes each invocation to a file named after its request ID in a local directory,
+/// e.g. when running the handler locally with [run_cli](crate::RocketHandlerBuilder::run_cli).
+///
+/// The directory is created if it does not exist. Errors writing files are logged to stderr.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectoryRecordingSink {
+    directory: PathBuf,
+}
+
+impl DirectoryRecordingSink {
+    /// Creates a sink that writes files to `directory`.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        DirectoryRecordingSink {
+            directory: directory.into(),
+        }
+    }
+}
+
+impl RecordingSink for DirectoryRecordingSink {
+    fn record(&self, invocation: &RecordedInvocation) {
+        let file_name: String = invocation
+            .request_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = self.directory.join(format!("{}.json", file_name));
+        let written = fs::create_dir_all(&self.directory)
+            .and_then(|_| fs::write(&path, format!("{:#}\n", invocation.to_json())));
+        if let Err(e) = written {
+            eprintln!("Could not record invocation to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// A `RecordingSink` that records invocations in memory, for use in tests.
+#[derive(Default, Debug)]
+pub struct InMemoryRecordingSink {
+    invocations: Mutex<Vec<RecordedInvocation>>,
+}
+
+impl InMemoryRecordingSink {
+    /// Gets all invocations that have been recorded, in the order they were recorded.
+    pub fn invocations(&self) -> Vec<RecordedInvocation> {
+        self.invocations.lock().unwrap().clone()
+    }
+}
+
+impl RecordingSink for InMemoryRecordingSink {
+    fn record(&self, invocation: &RecordedInvocation) {
+        self.invocations.lock().unwrap().push(invocation.clone());
+    }
+}
+
+impl<S: RecordingSink + ?Sized> RecordingSink for Arc<S> {
+    fn record(&self, invocation: &RecordedInvocation) {
+        (**self).record(invocation)
+    }
+}
+
+/// Reads recorded invocations from a file, or from every `.json` file in a directory in order of their names.
+///
+/// A file may contain several invocations, e.g. lines written by [StdoutRecordingSink] and copied from
+/// CloudWatch Logs. Any JSON values that are not recorded invocations are skipped.
+pub fn load_recordings(path: impl AsRef<Path>) -> io::Result<Vec<RecordedInvocation>> {
+    let path = path.as_ref();
+    let files = if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<_>>()?;
+        files.retain(|f| f.extension().map_or(false, |ext| ext == "json"));
+        files.sort();
+        files
+    } else {
+        vec![path.to_owned()]
+    };
+
+    let mut invocations = Vec::new();
+    for file in files {
+        let contents = fs::read_to_string(&file)?;
+        for value in Deserializer::from_str(&contents).into_iter::<Value>() {
+            let value = value.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            invocations.extend(RecordedInvocation::from_json(&value));
+        }
+    }
+    Ok(invocations)
+}
+
+/// Redacts the values of sensitive headers and query parameters, API Gateway authorizer claims and caller
+/// identities, and unless `record_bodies` is set, bodies from an event or response. API Gateway, ALB,
+/// VPC Lattice and Lambda@Edge events are supported.
+fn redact(value: &mut Value, headers: &[String], query_parameters: &[String], record_bodies: bool) {
+    let is_redacted_header = |name: &str| headers.iter().any(|h| h.eq_ignore_ascii_case(name));
+    redact_fields(value, "headers", is_redacted_header);
+    redact_fields(value, "multiValueHeaders", is_redacted_header);
+
+    let is_redacted_parameter = |name: &str| query_parameters.iter().any(|p| p == name);
+    redact_fields(value, "queryStringParameters", is_redacted_parameter);
+    redact_fields(
+        value,
+        "multiValueQueryStringParameters",
+        is_redacted_parameter,
+    );
+    redact_query_string(value, "rawQueryString", query_parameters);
+
+    // VPC Lattice events use snake_case names, and may include the query string in the path.
+    redact_fields(value, "query_string_parameters", is_redacted_parameter);
+    if let Some(raw_path) = value["raw_path"].as_str() {
+        value["raw_path"] = Value::String(access_log::redact_query(raw_path, query_parameters));
+    }
+
+    // HTTP API requests and responses pass cookies separately from the other headers.
+    let redact_cookies = is_redacted_header("cookie") || is_redacted_header("set-cookie");
+    if let Some(cookies) = value.get_mut("cookies").and_then(Value::as_array_mut) {
+        if redact_cookies {
+            *cookies = vec![json!(REDACTED); cookies.len()];
+        }
+    }
+
+    // The structure of these fields is kept, so that the event can still be parsed when it is replayed.
+    if let Some(context) = value.get_mut("requestContext") {
+        for key in &["authorizer", "identity"] {
+            if let Some(field) = context.get_mut(*key) {
+                redact_values(field);
+            }
+        }
+    }
+
+    if !record_bodies {
+        if let Some(body) = value.get_mut("body").filter(|b| b.is_string()) {
+            *body = json!(REDACTED);
+        }
+    }
+
+    // Lambda@Edge events include the request, and possibly the response, within each record.
+    for record in value
+        .get_mut("Records")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(request) = record.pointer_mut("/cf/request") {
+            redact_fields(request, "headers", is_redacted_header);
+            redact_query_string(request, "querystring", query_parameters);
+            if !record_bodies {
+                if let Some(data) = request.pointer_mut("/body/data") {
+                    *data = json!(REDACTED);
+                }
+            }
+        }
+        if let Some(response) = record.pointer_mut("/cf/response") {
+            redact_fields(response, "headers", is_redacted_header);
+        }
+    }
+}
+
+/// Redacts the values of query parameters in a field holding a query string without the `?`.
+fn redact_query_string(value: &mut Value, key: &str, query_parameters: &[String]) {
+    if let Some(query) = value[key].as_str() {
+        let redacted = access_log::redact_query(&format!("?{}", query), query_parameters);
+        value[key] = Value::String(redacted[1..].to_owned());
+    }
+}
+
+/// Replaces every string, number and boolean within `value` with `"REDACTED"`.
+fn redact_values(value: &mut Value) {
+    match value {
+        Value::Object(fields) => fields.values_mut().for_each(redact_values),
+        Value::Array(values) => values.iter_mut().for_each(redact_values),
+        Value::Null => {}
+        _ => *value = json!(REDACTED),
+    }
+}
+
+/// Redacts one of a header's values. Lambda@Edge headers are objects with the header's `key` and
+/// `value`, and only the value is redacted, so that the event can still be parsed.
+fn redact_entry(entry: &mut Value) {
+    match entry.get_mut("value") {
+        Some(value) => *value = json!(REDACTED),
+        None => *entry = json!(REDACTED),
+    }
+}
+
+fn redact_fields(value: &mut Value, key: &str, is_redacted: impl Fn(&str) -> bool) {
+    if let Some(fields) = value.get_mut(key).and_then(Value::as_object_mut) {
+        for (name, field) in fields.iter_mut() {
+            if is_redacted(name) {
+                match field {
+                    Value::Array(values) => values.iter_mut().for_each(redact_entry),
+                    _ => *field = json!(REDACTED),
+                }
+            }
+        }
+    }
+}
+
+/// Records a single invocation once it has completed.
+pub(crate) struct Recorder {
+    sink: Arc<dyn RecordingSink>,
+    redacted_headers: Vec<String>,
+    redacted_query_parameters: Vec<String>,
+    record_bodies: bool,
+    request_id: String,
+    time: SystemTime,
+    event: Value,
+}
+
+impl Recorder {
+    pub(crate) fn new(
+        sink: Arc<dyn RecordingSink>,
+        config: &Config,
+        request_id: String,
+        event: Value,
+    ) -> Recorder {
+        Recorder {
+            sink,
+            redacted_headers: config.redacted_headers.clone(),
+            redacted_query_parameters: config.redacted_query_parameters.clone(),
+            record_bodies: config.record_bodies,
+            request_id,
+            time: SystemTime::now(),
+            event,
+        }
+    }
+
+    pub(crate) fn record(mut self, result: &Result<Value, HandlerError>) {
+        let headers = &self.redacted_headers;
+        let query_parameters = &self.redacted_query_parameters;
+        redact(
+            &mut self.event,
+            headers,
+            query_parameters,
+            self.record_bodies,
+        );
+        let result = match result {
+            Ok(response) => {
+                let mut response = response.clone();
+                redact(&mut response, headers, query_parameters, self.record_bodies);
+                Ok(response)
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        self.sink.record(&RecordedInvocation {
+            request_id: self.request_id,
+            time: self.time,
+            event: self.event,
+            result,
+        });
+    }
+}
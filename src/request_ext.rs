@@ -1,58 +1,120 @@
+use crate::encoding::{decode_path, normalize_path};
 use http::header::HOST;
 use lambda_http::request::RequestContext;
 use lambda_http::{Request, RequestExt as _};
 
+/// The exact query string sent by the client, e.g. from an HTTP API's `rawQueryString`, captured
+/// from the raw event before `lambda_http` parses it into a map of parameters. This is stored in
+/// the request's extensions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct QueryString(pub(crate) String);
+
+/// The paths returned are always normalised into valid, percent-encoded URI paths, as `lambda_http`
+/// accepts some characters in a path that are not valid in a URI path, e.g. `|`.
 pub(crate) trait RequestExt {
     fn full_path(&self) -> String;
 
+    /// Like `full_path`, but returns `None` instead of panicking if the base path cannot be
+    /// determined from the request's resource path.
+    fn try_full_path(&self) -> Option<String>;
+
     fn base_path(&self) -> String;
 
-    fn api_path(&self) -> &str;
+    /// Like `base_path`, but returns `None` instead of panicking if the base path cannot be
+    /// determined from the request's resource path.
+    fn try_base_path(&self) -> Option<String>;
+
+    fn api_path(&self) -> String;
+
+    /// Like `api_path`, but returns `None` instead of panicking if the base path cannot be
+    /// determined from the request's resource path.
+    fn try_api_path(&self) -> Option<String>;
+
+    fn query_string(&self) -> Option<&QueryString>;
 }
 
 impl RequestExt for Request {
     fn full_path(&self) -> String {
-        if self.request_context().is_alb() || !is_default_api_gateway_url(self) {
-            self.uri().path().to_owned()
-        } else {
-            let mut path = self.base_path();
-            path.push_str(self.uri().path());
-            path
-        }
+        find_full_path(self).unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    fn try_full_path(&self) -> Option<String> {
+        find_full_path(self).ok()
     }
 
     fn base_path(&self) -> String {
-        match self.request_context() {
-            RequestContext::ApiGateway {
-                stage,
-                resource_path,
-                ..
-            } => {
-                if is_default_api_gateway_url(self) {
-                    format!("/{}", stage)
+        find_base_path(self).unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    fn try_base_path(&self) -> Option<String> {
+        find_base_path(self).ok()
+    }
+
+    fn api_path(&self) -> String {
+        find_api_path(self).unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    fn try_api_path(&self) -> Option<String> {
+        find_api_path(self).ok()
+    }
+
+    fn query_string(&self) -> Option<&QueryString> {
+        self.extensions().get::<QueryString>()
+    }
+}
+
+fn find_full_path(req: &Request) -> Result<String, String> {
+    if req.request_context().is_alb() || !is_default_api_gateway_url(req) {
+        Ok(normalize_path(req.uri().path()))
+    } else {
+        let mut path = find_base_path(req)?;
+        path.push_str(&normalize_path(req.uri().path()));
+        Ok(path)
+    }
+}
+
+fn find_api_path(req: &Request) -> Result<String, String> {
+    let path = normalize_path(req.uri().path());
+    if req.request_context().is_alb() || is_default_api_gateway_url(req) {
+        Ok(path)
+    } else {
+        Ok(path[find_base_path(req)?.len()..].to_owned())
+    }
+}
+
+/// Finds the base path of a request, or describes why it cannot be found.
+fn find_base_path(req: &Request) -> Result<String, String> {
+    match req.request_context() {
+        RequestContext::ApiGateway {
+            stage,
+            resource_path,
+            ..
+        } => {
+            if is_default_api_gateway_url(req) {
+                // The `$default` stage of an HTTP API is served without a stage prefix.
+                if stage == "$default" {
+                    Ok(String::new())
                 } else {
-                    let resource_path = populate_resource_path(self, resource_path);
-                    let full_path = self.uri().path();
-                    let resource_path_index =
-                        full_path.rfind(&resource_path).unwrap_or_else(|| {
-                            panic!(
-                                "Could not find segment '{}' in path '{}'.",
-                                resource_path, full_path
-                            )
-                        });
-                    full_path[..resource_path_index].to_owned()
+                    Ok(normalize_path(&format!("/{}", stage)))
                 }
+            } else {
+                // Path parameters are decoded, so they must be compared against the decoded path.
+                let resource_path = populate_resource_path(req, resource_path)?;
+                let full_path = req.uri().path();
+                let decoded_path = decode_path(full_path);
+                let resource_path_index = decoded_path.rfind(&resource_path).ok_or_else(|| {
+                    format!(
+                        "Could not find segment '{}' in path '{}'.",
+                        resource_path, decoded_path
+                    )
+                })?;
+                // Decoding never adds or removes slashes, so the base path is the same
+                // number of segments of the (encoded) full path.
+                let base_segments = decoded_path[..resource_path_index].matches('/').count();
+                Ok(normalize_path(segments_prefix(full_path, base_segments)))
             }
-            RequestContext::Alb { .. } => String::new(),
-        }
-    }
-
-    fn api_path(&self) -> &str {
-        if self.request_context().is_alb() || is_default_api_gateway_url(self) {
-            self.uri().path()
-        } else {
-            &self.uri().path()[self.base_path().len()..]
         }
+        RequestContext::Alb { .. } => Ok(String::new()),
     }
 }
 
@@ -64,21 +126,37 @@ fn is_default_api_gateway_url(req: &Request) -> bool {
         .unwrap_or(false)
 }
 
-fn populate_resource_path(req: &Request, resource_path: String) -> String {
+/// Replaces the parameters in `resource_path` with their values, or describes which parameter is missing.
+fn populate_resource_path(req: &Request, resource_path: String) -> Result<String, String> {
     let path_parameters = req.path_parameters();
-    resource_path
+    let segments = resource_path
         .split('/')
         .map(|segment| {
             if segment.starts_with('{') {
-                let end = if segment.ends_with("+}") { 2 } else { 1 };
+                let greedy = segment.ends_with("+}");
+                let end = if greedy { 2 } else { 1 };
                 let param = &segment[1..segment.len() - end];
-                path_parameters
+                let value = path_parameters
                     .get(param)
-                    .unwrap_or_else(|| panic!("Could not find path parameter '{}'.", param))
+                    .ok_or_else(|| format!("Could not find path parameter '{}'.", param))?;
+                // A slash in a non-greedy parameter must have been encoded in the path.
+                if greedy {
+                    Ok(value.to_owned())
+                } else {
+                    Ok(value.replace('/', "%2F"))
+                }
             } else {
-                segment
+                Ok(segment.to_owned())
             }
         })
-        .collect::<Vec<&str>>()
-        .join("/")
+        .collect::<Result<Vec<String>, String>>()?;
+    Ok(segments.join("/"))
+}
+
+/// Gets the prefix of `path` containing its first `segments` segments, e.g. `/a/b` for 2 segments of `/a/b/c`.
+fn segments_prefix(path: &str, segments: usize) -> &str {
+    match path.match_indices('/').nth(segments) {
+        Some((index, _)) => &path[..index],
+        None => path,
+    }
 }
@@ -0,0 +1,135 @@
+use crate::http_api::is_http_api_v2_event;
+use lambda_http::{Body, Response};
+use serde_json::{json, Map, Value};
+
+/// The format that a response to an HTTP event must be returned in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ResponseFormat {
+    /// An API Gateway proxy integration response, with both `headers` and `multiValueHeaders`.
+    ApiGateway,
+    /// An API Gateway HTTP API response for payload format version 2.0, with `headers` and `cookies`.
+    HttpApiV2,
+    /// An Application Load Balancer response. If the target group has multi-value headers
+    /// enabled, only `multiValueHeaders` may be set, otherwise only `headers` may be set.
+    Alb { multi_value: bool },
+}
+
+impl ResponseFormat {
+    /// Detects the response format from the incoming event. ALB target groups with multi-value
+    /// headers enabled send `multiValueHeaders` instead of `headers`, and expect the same in the response.
+    pub(crate) fn detect(event: &Value) -> ResponseFormat {
+        if is_http_api_v2_event(event) {
+            ResponseFormat::HttpApiV2
+        } else if event["requestContext"]["elb"].is_object() {
+            ResponseFormat::Alb {
+                multi_value: event["multiValueHeaders"].is_object(),
+            }
+        } else {
+            ResponseFormat::ApiGateway
+        }
+    }
+}
+
+/// Serializes a response into the format understood by API Gateway or an Application Load Balancer.
+pub(crate) fn to_json(res: &Response<Body>, format: ResponseFormat) -> Value {
+    let mut headers = Map::new();
+    let mut multi_value_headers = Map::new();
+    let mut joined_headers = Map::new();
+    let mut cookies = Vec::new();
+    for name in res.headers().keys() {
+        let values: Vec<String> = res
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+            .collect();
+        if let Some(last) = values.last() {
+            headers.insert(name.to_string(), Value::String(last.clone()));
+        }
+        if *name == http::header::SET_COOKIE {
+            cookies.extend(values.iter().cloned().map(Value::String));
+        } else {
+            joined_headers.insert(name.to_string(), Value::String(values.join(",")));
+        }
+        multi_value_headers.insert(
+            name.to_string(),
+            Value::Array(values.into_iter().map(Value::String).collect()),
+        );
+    }
+
+    let (body, is_base64_encoded) = match res.body() {
+        Body::Empty => (Value::Null, false),
+        Body::Text(s) => (Value::String(s.clone()), false),
+        Body::Binary(b) => (Value::String(base64::encode(b)), true),
+    };
+
+    let mut value = json!({
+        "statusCode": res.status().as_u16(),
+        "body": body,
+        "isBase64Encoded": is_base64_encoded,
+    });
+    match format {
+        ResponseFormat::ApiGateway => {
+            value["headers"] = Value::Object(headers);
+            value["multiValueHeaders"] = Value::Object(multi_value_headers);
+        }
+        ResponseFormat::HttpApiV2 => {
+            value["headers"] = Value::Object(joined_headers);
+            if !cookies.is_empty() {
+                value["cookies"] = Value::Array(cookies);
+            }
+        }
+        ResponseFormat::Alb { multi_value } => {
+            value["statusDescription"] = Value::String(format!(
+                "{} {}",
+                res.status().as_u16(),
+                res.status().canonical_reason().unwrap_or_default()
+            ));
+            if multi_value {
+                value["multiValueHeaders"] = Value::Object(multi_value_headers);
+            } else {
+                value["headers"] = Value::Object(headers);
+            }
+        }
+    }
+    value
+}
+
+/// Reads the headers of a response in any of the JSON formats, as pairs of lowercase names and values.
+pub(crate) fn headers_from_json(value: &Value) -> Vec<(String, String)> {
+    let mut headers = Vec::new();
+    if let Some(multi) = value["multiValueHeaders"].as_object() {
+        for (name, values) in multi {
+            for v in values
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                headers.push((name.to_lowercase(), v.to_owned()));
+            }
+        }
+    } else if let Some(single) = value["headers"].as_object() {
+        for (name, v) in single {
+            if let Some(v) = v.as_str() {
+                headers.push((name.to_lowercase(), v.to_owned()));
+            }
+        }
+    }
+    for cookie in value["cookies"].as_array().into_iter().flatten() {
+        if let Some(cookie) = cookie.as_str() {
+            headers.push(("set-cookie".to_owned(), cookie.to_owned()));
+        }
+    }
+    headers
+}
+
+/// Decodes the body of a response in any of the JSON formats.
+pub(crate) fn body_from_json(value: &Value) -> Result<Vec<u8>, base64::DecodeError> {
+    let body = value["body"].as_str().unwrap_or_default();
+    if value["isBase64Encoded"].as_bool().unwrap_or_default() {
+        base64::decode(body)
+    } else {
+        Ok(body.as_bytes().to_vec())
+    }
+}
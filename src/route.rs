@@ -0,0 +1,56 @@
+use crate::method::route_matches_path;
+use rocket::http::Method;
+use rocket::Route;
+
+/// The Rocket route that handled a request, stored in the extensions of the Lambda response so
+/// that metrics and traces can be grouped by route rather than by path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct MatchedRoute {
+    pub(crate) name: Option<&'static str>,
+    pub(crate) method: Method,
+    pub(crate) uri: String,
+}
+
+impl MatchedRoute {
+    pub(crate) fn new(route: &Route) -> MatchedRoute {
+        MatchedRoute {
+            name: route.name,
+            method: route.method,
+            uri: route.uri.to_string(),
+        }
+    }
+
+    /// Identifies the route by its method and URI, e.g. `GET /items/<id>`.
+    pub(crate) fn id(&self) -> String {
+        format!("{} {}", self.method, self.uri)
+    }
+}
+
+/// The route that a request is expected to be dispatched to, found by [expected_route] when the
+/// request is created and stored in its local cache.
+pub(crate) struct ExpectedRoute(pub(crate) Option<MatchedRoute>);
+
+/// Finds the route that Rocket is expected to dispatch a request to: the mounted route with the best
+/// rank whose method and path match the request. `HEAD` requests fall back to `GET` routes, as in
+/// Rocket. Queries, formats and guards that forward are not considered.
+pub(crate) fn expected_route<'a>(
+    routes: impl Iterator<Item = &'a Route>,
+    method: Method,
+    path: &str,
+) -> Option<&'a Route> {
+    let mut best: Option<&Route> = None;
+    let mut best_get: Option<&Route> = None;
+    for route in routes.filter(|route| route_matches_path(route, path)) {
+        let slot = if route.method == method {
+            &mut best
+        } else if method == Method::Head && route.method == Method::Get {
+            &mut best_get
+        } else {
+            continue;
+        };
+        if slot.map_or(true, |r| route.rank < r.rank) {
+            *slot = Some(route);
+        }
+    }
+    best.or(best_get)
+}
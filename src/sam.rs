@@ -0,0 +1,249 @@
+use crate::config::Config;
+use crate::openapi::{api_routes, binary_media_types};
+use rocket::Route;
+use serde_json::{json, Map, Value};
+
+/// The event source of the function in a template generated by
+/// [sam_template](crate::RocketHandlerBuilder::sam_template).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SamEventSource {
+    /// An API Gateway REST API, with `BinaryMediaTypes` derived from the configured response types.
+    RestApi,
+    /// An API Gateway HTTP API, using payload format version 2.0.
+    HttpApi,
+    /// An Application Load Balancer target group, with listener rules added to the listener given by the
+    /// template's `ListenerArn` parameter.
+    Alb {
+        /// The priority of the first listener rule, with each following rule taking the next priority.
+        /// Priorities must be unique within a listener, so this must leave room for the listener's other rules.
+        base_priority: u32,
+    },
+}
+
+/// Determines which requests are forwarded to the function in a template generated by
+/// [sam_template](crate::RocketHandlerBuilder::sam_template).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SamRoutes {
+    /// Forwards only the mounted routes, so that requests to other paths are rejected before they
+    /// reach the function.
+    Mounted,
+    /// Forwards every request with a greedy `{proxy+}` path, and lets Rocket respond to unknown paths.
+    Greedy,
+}
+
+/// Generates a SAM template with the function and the resources for its event source.
+pub(crate) fn template<'a>(
+    routes: impl Iterator<Item = &'a Route>,
+    config: &Config,
+    function_id: &str,
+    source: SamEventSource,
+    sam_routes: SamRoutes,
+) -> Value {
+    // Each entry is an event name, an API Gateway path and a lowercase method.
+    let events: Vec<(String, String, String)> = match sam_routes {
+        SamRoutes::Mounted => {
+            let mut names = Vec::new();
+            api_routes(routes)
+                .into_iter()
+                .map(|api_route| {
+                    let method = api_route.route.method.as_str().to_lowercase();
+                    let name = unique_name(&mut names, event_name(&method, &api_route.path));
+                    (name, api_route.path, method)
+                })
+                .collect()
+        }
+        SamRoutes::Greedy => vec![
+            ("Root".to_owned(), "/".to_owned(), "any".to_owned()),
+            ("Proxy".to_owned(), "/{proxy+}".to_owned(), "any".to_owned()),
+        ],
+    };
+
+    let mut function = json!({
+        "Type": "AWS::Serverless::Function",
+        "Properties": {
+            "CodeUri": ".",
+            "Handler": "bootstrap",
+            "Runtime": "provided.al2",
+        },
+    });
+    let mut resources = Map::new();
+    let mut template = json!({
+        "AWSTemplateFormatVersion": "2010-09-09",
+        "Transform": "AWS::Serverless-2016-10-31",
+    });
+
+    match source {
+        SamEventSource::RestApi => {
+            let api_id = format!("{}Api", function_id);
+            let function_events: Map<String, Value> = events
+                .into_iter()
+                .map(|(name, path, method)| {
+                    let event = json!({
+                        "Type": "Api",
+                        "Properties": {
+                            "RestApiId": { "Ref": api_id },
+                            "Path": path,
+                            "Method": method,
+                        },
+                    });
+                    (name, event)
+                })
+                .collect();
+            function["Properties"]["Events"] = Value::Object(function_events);
+
+            let mut api = json!({
+                "Type": "AWS::Serverless::Api",
+                "Properties": { "StageName": "Prod" },
+            });
+            // SAM requires slashes in binary media types to be escaped as `~1`.
+            let media_types: Vec<String> = binary_media_types(config)
+                .iter()
+                .map(|media_type| media_type.replace('/', "~1"))
+                .collect();
+            if !media_types.is_empty() {
+                api["Properties"]["BinaryMediaTypes"] = json!(media_types);
+            }
+            resources.insert(api_id, api);
+        }
+        SamEventSource::HttpApi => {
+            let function_events: Map<String, Value> = match sam_routes {
+                // An event without a path or method is the `$default` route, which matches every request.
+                SamRoutes::Greedy => vec![("Default".to_owned(), json!({ "Type": "HttpApi" }))]
+                    .into_iter()
+                    .collect(),
+                SamRoutes::Mounted => events
+                    .into_iter()
+                    .map(|(name, path, method)| {
+                        let event = json!({
+                            "Type": "HttpApi",
+                            "Properties": { "Path": path, "Method": method },
+                        });
+                        (name, event)
+                    })
+                    .collect(),
+            };
+            function["Properties"]["Events"] = Value::Object(function_events);
+        }
+        SamEventSource::Alb { base_priority } => {
+            template["Parameters"] = json!({
+                "ListenerArn": {
+                    "Type": "String",
+                    "Description": "The ARN of the load balancer listener to add rules to",
+                },
+            });
+            let target_group_id = format!("{}TargetGroup", function_id);
+            let permission_id = format!("{}LoadBalancerPermission", function_id);
+            resources.insert(
+                permission_id.clone(),
+                json!({
+                    "Type": "AWS::Lambda::Permission",
+                    "Properties": {
+                        "Action": "lambda:InvokeFunction",
+                        "FunctionName": { "Fn::GetAtt": [function_id, "Arn"] },
+                        "Principal": "elasticloadbalancing.amazonaws.com",
+                    },
+                }),
+            );
+            resources.insert(
+                target_group_id.clone(),
+                json!({
+                    "Type": "AWS::ElasticLoadBalancingV2::TargetGroup",
+                    "DependsOn": permission_id,
+                    "Properties": {
+                        "TargetType": "lambda",
+                        "Targets": [{ "Id": { "Fn::GetAtt": [function_id, "Arn"] } }],
+                    },
+                }),
+            );
+
+            let rules: Vec<(String, Vec<Value>)> = match sam_routes {
+                SamRoutes::Greedy => vec![(
+                    "Proxy".to_owned(),
+                    vec![
+                        json!({ "Field": "path-pattern", "PathPatternConfig": { "Values": ["/*"] } }),
+                    ],
+                )],
+                SamRoutes::Mounted => events
+                    .into_iter()
+                    .map(|(name, path, method)| {
+                        let conditions = vec![
+                            json!({
+                                "Field": "path-pattern",
+                                "PathPatternConfig": { "Values": [alb_path_pattern(&path)] },
+                            }),
+                            json!({
+                                "Field": "http-request-method",
+                                "HttpRequestMethodConfig": { "Values": [method.to_uppercase()] },
+                            }),
+                        ];
+                        (name, conditions)
+                    })
+                    .collect(),
+            };
+            for (index, (name, conditions)) in rules.into_iter().enumerate() {
+                resources.insert(
+                    format!("{}{}Rule", function_id, name),
+                    json!({
+                        "Type": "AWS::ElasticLoadBalancingV2::ListenerRule",
+                        "Properties": {
+                            "ListenerArn": { "Ref": "ListenerArn" },
+                            "Priority": base_priority + index as u32,
+                            "Conditions": conditions,
+                            "Actions": [{
+                                "Type": "forward",
+                                "TargetGroupArn": { "Ref": target_group_id },
+                            }],
+                        },
+                    }),
+                );
+            }
+        }
+    }
+
+    resources.insert(function_id.to_owned(), function);
+    template["Resources"] = Value::Object(resources);
+    template
+}
+
+/// Converts an API Gateway path into an ALB path pattern, where each parameter matches any
+/// characters, e.g. `/items/{id}` becomes `/items/*`.
+fn alb_path_pattern(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.starts_with('{') {
+                "*"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Names an event after its method and path, e.g. `GetItemsId` for `GET /items/{id}`.
+fn event_name(method: &str, path: &str) -> String {
+    let mut name = String::new();
+    for word in std::iter::once(method).chain(path.split(|c: char| !c.is_ascii_alphanumeric())) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            name.extend(chars);
+        }
+    }
+    if path == "/" {
+        name.push_str("Root");
+    }
+    name
+}
+
+/// Adds a number to a name if it has already been used, as logical IDs must be unique.
+fn unique_name(names: &mut Vec<String>, name: String) -> String {
+    let mut unique = name.clone();
+    let mut n = 1;
+    while names.contains(&unique) {
+        n += 1;
+        unique = format!("{}{}", name, n);
+    }
+    names.push(unique.clone());
+    unique
+}
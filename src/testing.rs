@@ -0,0 +1,612 @@
+/*!
+Helpers for testing a [RocketHandler](crate::RocketHandler) without deploying it, available with the `testing` feature.
+
+[EventBuilder] builds the JSON events that API Gateway and Application Load Balancers send to a Lambda function,
+and [TestResponse] reads the response regardless of whether its body was returned as text or base64-encoded binary.
+[MockRuntimeApi] runs a local Lambda Runtime API, so that [launch_raw()](crate::RocketHandlerBuilder::launch_raw) can be
+tested end-to-end through the real runtime loop, and [InMemoryAccessLog] collects the entries of an
+[access log](crate::RocketHandlerBuilder::access_log) so that they can be asserted on.
+
+# Example
+
+```rust
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use] extern crate rocket;
+use rocket_lamb::testing::EventBuilder;
+use rocket_lamb::RocketExt;
+
+#[post("/upper", data = "<body>")]
+fn upper(body: String) -> String {
+    body.to_uppercase()
+}
+
+fn main() {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .into_handler();
+
+    EventBuilder::http_api("POST", "/upper")
+        .header("content-type", "text/plain")
+        .body("hello")
+        .run(&mut handler)
+        .assert_status(200)
+        .assert_body("HELLO");
+}
+```
+*/
+
+mod access_log;
+mod runtime_api;
+
+pub use access_log::InMemoryAccessLog;
+pub use runtime_api::{InvocationResult, MockRuntimeApi};
+
+use crate::encoding::{decode_path, encode_query_component, push_query_pair};
+use crate::event::LambdaEvent;
+use crate::handler::{local_context, RocketHandler};
+use crate::response;
+use lambda_http::{Body, Request, Response};
+use lambda_runtime::Context;
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+const DEFAULT_HOST: &str = "abcdef1234.execute-api.eu-west-1.amazonaws.com";
+const CUSTOM_DOMAIN_HOST: &str = "example.com";
+const SOURCE_IP: &str = "1.2.3.4";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum EventKind {
+    Rest,
+    HttpApi,
+    Alb,
+}
+
+/// Builds the event that a Lambda function receives for an HTTP request.
+///
+/// Paths are given as the client sees them after any stage or custom domain base path, e.g. `/upper/one`.
+/// Path parameters for the default `/{proxy+}` resource are filled in automatically.
+#[derive(Clone, Debug)]
+pub struct EventBuilder {
+    kind: EventKind,
+    method: String,
+    path: String,
+    base_path: String,
+    stage: String,
+    host: String,
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    body: Option<(String, bool)>,
+    claims: Map<String, Value>,
+    path_parameters: Map<String, Value>,
+    stage_variables: Map<String, Value>,
+    resource: Option<String>,
+}
+
+impl EventBuilder {
+    fn new(kind: EventKind, method: &str, path: &str, stage: &str, host: &str) -> Self {
+        EventBuilder {
+            kind,
+            method: method.to_uppercase(),
+            path: path.to_owned(),
+            base_path: String::new(),
+            stage: stage.to_owned(),
+            host: host.to_owned(),
+            headers: Vec::new(),
+            query: Vec::new(),
+            body: None,
+            claims: Map::new(),
+            path_parameters: Map::new(),
+            stage_variables: Map::new(),
+            resource: None,
+        }
+    }
+
+    /// Creates an event for an API Gateway REST API request to the default `execute-api` URL of the `Prod` stage.
+    pub fn rest(method: &str, path: &str) -> Self {
+        Self::new(EventKind::Rest, method, path, "Prod", DEFAULT_HOST)
+    }
+
+    /// Creates an event for an API Gateway HTTP API request (payload format version 2.0) to the default
+    /// `execute-api` URL of the `$default` stage.
+    pub fn http_api(method: &str, path: &str) -> Self {
+        Self::new(EventKind::HttpApi, method, path, "$default", DEFAULT_HOST)
+    }
+
+    /// Creates an event for an Application Load Balancer request to a target group without multi-value headers.
+    pub fn alb(method: &str, path: &str) -> Self {
+        Self::new(EventKind::Alb, method, path, "", CUSTOM_DOMAIN_HOST)
+    }
+
+    /// Creates an event for an API Gateway REST API request through a custom domain, where the API is mapped to
+    /// `base_path`. An empty `base_path` maps the API to the root of the domain.
+    pub fn custom_domain(method: &str, path: &str, base_path: &str) -> Self {
+        let mut builder = Self::new(EventKind::Rest, method, path, "Prod", CUSTOM_DOMAIN_HOST);
+        builder.base_path = base_path.trim_end_matches('/').to_owned();
+        if !builder.base_path.is_empty() && !builder.base_path.starts_with('/') {
+            builder.base_path.insert(0, '/');
+        }
+        builder
+    }
+
+    /// Sets the API Gateway stage.
+    pub fn stage(mut self, stage: &str) -> Self {
+        self.stage = stage.to_owned();
+        self
+    }
+
+    /// Sets the `Host` header. Hosts other than an `execute-api` URL are treated as a custom domain.
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = host.to_owned();
+        self
+    }
+
+    /// Adds a request header. Calling this more than once with the same name adds multiple values.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Adds a decoded query string parameter. Calling this more than once with the same key adds multiple values.
+    pub fn query(mut self, key: &str, value: &str) -> Self {
+        self.query.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Sets a text request body.
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = Some((body.to_owned(), false));
+        self
+    }
+
+    /// Sets a binary request body, which is sent base64-encoded.
+    pub fn binary_body(mut self, body: &[u8]) -> Self {
+        self.body = Some((base64::encode(body), true));
+        self
+    }
+
+    /// Adds a claim to the request's authorizer, as set by a Cognito or JWT authorizer.
+    /// Application Load Balancer events do not have an authorizer, so this is ignored for them.
+    pub fn authorizer_claim(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.claims.insert(name.to_owned(), value.into());
+        self
+    }
+
+    /// Adds a path parameter. Setting any path parameter replaces the default `proxy` parameter.
+    pub fn path_parameter(mut self, name: &str, value: &str) -> Self {
+        self.path_parameters
+            .insert(name.to_owned(), Value::String(value.to_owned()));
+        self
+    }
+
+    /// Adds a stage variable.
+    pub fn stage_variable(mut self, name: &str, value: &str) -> Self {
+        self.stage_variables
+            .insert(name.to_owned(), Value::String(value.to_owned()));
+        self
+    }
+
+    /// Sets the resource (or route) path, e.g. `/users/{id}`. The default is `/{proxy+}`, or `/` for the root path.
+    pub fn resource(mut self, resource: &str) -> Self {
+        self.resource = Some(resource.to_owned());
+        self
+    }
+
+    fn is_default_host(&self) -> bool {
+        self.host.ends_with(".amazonaws.com") && self.host.contains(".execute-api.")
+    }
+
+    fn resource_and_parameters(&self) -> (String, Map<String, Value>) {
+        let default_resource = if self.path == "/" { "/" } else { "/{proxy+}" };
+        let resource = self.resource.as_deref().unwrap_or(default_resource);
+        let mut path_parameters = self.path_parameters.clone();
+        if resource == "/{proxy+}" && path_parameters.is_empty() {
+            let proxy = decode_path(self.path.trim_start_matches('/'));
+            path_parameters.insert("proxy".to_owned(), Value::String(proxy));
+        }
+        (resource.to_owned(), path_parameters)
+    }
+
+    fn encoded_query(&self) -> String {
+        let mut query = String::new();
+        for (key, value) in &self.query {
+            let value = encode_query_component(value);
+            push_query_pair(
+                &mut query,
+                &encode_query_component(key),
+                Some(value.as_str()).filter(|v| !v.is_empty()),
+            );
+        }
+        query
+    }
+
+    fn header_values(&self) -> Map<String, Value> {
+        let mut headers = Map::new();
+        for (name, value) in &self.headers {
+            let values = headers
+                .entry(name.clone())
+                .or_insert_with(|| Value::Array(vec![]));
+            if let Value::Array(values) = values {
+                values.push(Value::String(value.clone()));
+            }
+        }
+        headers
+    }
+
+    fn body_fields(&self, event: &mut Value) {
+        let (body, is_base64_encoded) = match &self.body {
+            Some((body, is_base64_encoded)) => (Value::String(body.clone()), *is_base64_encoded),
+            None => (Value::Null, false),
+        };
+        event["body"] = body;
+        event["isBase64Encoded"] = Value::Bool(is_base64_encoded);
+    }
+
+    /// Builds the JSON event, as received by [launch_raw()](crate::RocketHandlerBuilder::launch_raw).
+    pub fn to_json(&self) -> Value {
+        let has_host = self
+            .headers
+            .iter()
+            .any(|(n, _)| n.eq_ignore_ascii_case("host"));
+        let builder = if has_host {
+            self.clone()
+        } else {
+            self.clone().header("host", &self.host)
+        };
+        let mut event = match self.kind {
+            EventKind::Rest => builder.rest_event(),
+            EventKind::HttpApi => builder.http_api_event(),
+            EventKind::Alb => builder.alb_event(),
+        };
+        builder.body_fields(&mut event);
+        event
+    }
+
+    fn rest_event(&self) -> Value {
+        let (resource, path_parameters) = self.resource_and_parameters();
+        let path = format!("{}{}", self.base_path, self.path);
+        let context_path = if self.is_default_host() {
+            format!("/{}{}", self.stage, self.path)
+        } else {
+            path.clone()
+        };
+
+        let headers = self.header_values();
+        let single_headers: Map<String, Value> = headers
+            .iter()
+            .map(|(k, v)| (k.clone(), v[0].clone()))
+            .collect();
+        let mut query = Map::new();
+        for (key, value) in &self.query {
+            let values = query.entry(key.clone()).or_insert_with(|| json!([]));
+            if let Value::Array(values) = values {
+                values.push(Value::String(value.clone()));
+            }
+        }
+        let single_query: Map<String, Value> = query
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), v.as_array()?.last()?.clone())))
+            .collect();
+
+        let mut context = json!({
+            "resourceId": "abc123",
+            "resourcePath": resource,
+            "httpMethod": self.method,
+            "path": context_path,
+            "accountId": "123456789012",
+            "protocol": "HTTP/1.1",
+            "stage": self.stage,
+            "requestId": "test-request-id",
+            "identity": {
+                "sourceIp": SOURCE_IP,
+            },
+            "domainName": self.host,
+            "apiId": "abcdef1234",
+        });
+        if !self.claims.is_empty() {
+            context["authorizer"] = json!({ "claims": self.claims });
+        }
+
+        json!({
+            "resource": resource,
+            "path": path,
+            "httpMethod": self.method,
+            "headers": single_headers,
+            "multiValueHeaders": headers,
+            "queryStringParameters": single_query,
+            "multiValueQueryStringParameters": query,
+            "pathParameters": non_empty(path_parameters),
+            "stageVariables": non_empty(self.stage_variables.clone()),
+            "requestContext": context,
+        })
+    }
+
+    fn http_api_event(&self) -> Value {
+        let raw_path = if self.stage == "$default" || !self.is_default_host() {
+            format!("{}{}", self.base_path, self.path)
+        } else {
+            format!("/{}{}", self.stage, self.path)
+        };
+        let route_key = match &self.resource {
+            Some(resource) => format!("{} {}", self.method, resource),
+            None => "$default".to_owned(),
+        };
+
+        // HTTP APIs lowercase header names, join repeated headers with commas, and pass cookies separately.
+        let mut headers = Map::new();
+        let mut cookies = Vec::new();
+        for (name, value) in &self.headers {
+            let name = name.to_lowercase();
+            if name == "cookie" {
+                cookies.extend(value.split(';').map(|c| Value::String(c.trim().to_owned())));
+                continue;
+            }
+            let joined = match headers.get(&name).and_then(Value::as_str) {
+                Some(existing) => format!("{},{}", existing, value),
+                None => value.clone(),
+            };
+            headers.insert(name, Value::String(joined));
+        }
+        let mut query = Map::new();
+        for (key, value) in &self.query {
+            let joined = match query.get(key).and_then(Value::as_str) {
+                Some(existing) => format!("{},{}", existing, value),
+                None => value.clone(),
+            };
+            query.insert(key.clone(), Value::String(joined));
+        }
+
+        let mut context = json!({
+            "accountId": "123456789012",
+            "apiId": "abcdef1234",
+            "domainName": self.host,
+            "http": {
+                "method": self.method,
+                "path": raw_path,
+                "protocol": "HTTP/1.1",
+                "sourceIp": SOURCE_IP,
+                "userAgent": "rocket_lamb",
+            },
+            "requestId": "test-request-id",
+            "routeKey": route_key,
+            "stage": self.stage,
+        });
+        if !self.claims.is_empty() {
+            context["authorizer"] = json!({ "jwt": { "claims": self.claims, "scopes": null } });
+        }
+
+        let mut event = json!({
+            "version": "2.0",
+            "routeKey": route_key,
+            "rawPath": raw_path,
+            "rawQueryString": self.encoded_query(),
+            "headers": headers,
+            "requestContext": context,
+        });
+        if !cookies.is_empty() {
+            event["cookies"] = Value::Array(cookies);
+        }
+        if !query.is_empty() {
+            event["queryStringParameters"] = Value::Object(query);
+        }
+        if !self.path_parameters.is_empty() {
+            event["pathParameters"] = Value::Object(self.path_parameters.clone());
+        }
+        if !self.stage_variables.is_empty() {
+            event["stageVariables"] = Value::Object(self.stage_variables.clone());
+        }
+        event
+    }
+
+    fn alb_event(&self) -> Value {
+        // Load balancers pass header names and query parameters exactly as the client sent them,
+        // keeping the last of any repeated values.
+        let headers: Map<String, Value> = self
+            .headers
+            .iter()
+            .map(|(k, v)| (k.to_lowercase(), Value::String(v.clone())))
+            .collect();
+        let query: Map<String, Value> = self
+            .query
+            .iter()
+            .map(|(k, v)| {
+                (
+                    encode_query_component(k),
+                    Value::String(encode_query_component(v)),
+                )
+            })
+            .collect();
+
+        json!({
+            "requestContext": {
+                "elb": {
+                    "targetGroupArn": "arn:aws:elasticloadbalancing:eu-west-1:123456789012:targetgroup/test/0123456789abcdef"
+                }
+            },
+            "httpMethod": self.method,
+            "path": self.path,
+            "headers": headers,
+            "queryStringParameters": query,
+        })
+    }
+
+    /// Builds the event and parses it into a `lambda_http` `Request`, to be passed to
+    /// [RocketHandler::run](lambda_http::Handler::run).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event could not be parsed.
+    pub fn build(&self) -> Request {
+        match LambdaEvent::from_value(self.to_json()) {
+            Ok(LambdaEvent::Http(req, _)) => req,
+            Ok(event) => panic!("Expected an HTTP event, but got {}", event.describe()),
+            Err(e) => panic!("Could not parse event: {}", e),
+        }
+    }
+
+    /// Runs the event through `handler` as a raw JSON event with a [mock_context], returning the response.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler returns an error.
+    pub fn run(&self, handler: &mut RocketHandler) -> TestResponse {
+        use lambda_runtime::Handler;
+
+        match handler.run(self.to_json(), mock_context()) {
+            Ok(res) => TestResponse::from_json(&res),
+            Err(e) => panic!("Handler returned an error: {:?}", e),
+        }
+    }
+}
+
+fn non_empty(map: Map<String, Value>) -> Value {
+    if map.is_empty() {
+        Value::Null
+    } else {
+        Value::Object(map)
+    }
+}
+
+/// Creates a Lambda `Context` for tests, with a request ID of `test-request-id` and a deadline 30 seconds from now.
+pub fn mock_context() -> Context {
+    Context {
+        function_name: "test-function".to_owned(),
+        invoked_function_arn: "arn:aws:lambda:eu-west-1:123456789012:function:test-function"
+            .to_owned(),
+        log_group_name: "/aws/lambda/test-function".to_owned(),
+        log_stream_name: "test-log-stream".to_owned(),
+        ..local_context("test-request-id".to_owned(), Duration::from_secs(30))
+    }
+}
+
+/// A response returned by a [RocketHandler], with its body decoded.
+///
+/// The assertion methods panic with a descriptive message when they fail, and return the
+/// response so that they can be chained.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl TestResponse {
+    /// Reads a JSON response in the format used by API Gateway REST APIs, HTTP APIs or Application Load Balancers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the response does not have a valid `statusCode`, or if a base64-encoded body could
+    /// not be decoded.
+    pub fn from_json(value: &Value) -> TestResponse {
+        let status = value["statusCode"]
+            .as_u64()
+            .filter(|status| (100..1000).contains(status))
+            .unwrap_or_else(|| panic!("Response has no valid statusCode: {}", value));
+        TestResponse {
+            status: status as u16,
+            headers: response::headers_from_json(value),
+            body: response::body_from_json(value).expect("Response body is not valid base64"),
+        }
+    }
+
+    /// Gets the status code.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Gets the first value of a header, ignoring the case of its name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_values(name).into_iter().next()
+    }
+
+    /// Gets all values of a header, ignoring the case of its name.
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// Gets all headers as pairs of lowercase names and values.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Gets the decoded body.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Gets the decoded body as a string, replacing any invalid UTF-8 sequences.
+    pub fn body_string(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Asserts that the response has the given status code.
+    pub fn assert_status(&self, status: u16) -> &Self {
+        assert_eq!(
+            self.status,
+            status,
+            "Unexpected status code, response body: {}",
+            self.body_string()
+        );
+        self
+    }
+
+    /// Asserts that the response has a header with the given value.
+    pub fn assert_header(&self, name: &str, value: &str) -> &Self {
+        let values = self.header_values(name);
+        assert!(
+            values.contains(&value),
+            "Expected header '{}' to have value '{}', but got {:?}",
+            name,
+            value,
+            values
+        );
+        self
+    }
+
+    /// Asserts that the response does not have a header.
+    pub fn assert_no_header(&self, name: &str) -> &Self {
+        let values = self.header_values(name);
+        assert!(
+            values.is_empty(),
+            "Expected no header '{}', but got {:?}",
+            name,
+            values
+        );
+        self
+    }
+
+    /// Asserts that the decoded body is equal to `body`.
+    pub fn assert_body(&self, body: impl AsRef<[u8]>) -> &Self {
+        let body = body.as_ref();
+        assert!(
+            self.body == body,
+            "Expected body {:?}, but got {:?}",
+            String::from_utf8_lossy(body),
+            self.body_string()
+        );
+        self
+    }
+}
+
+impl From<Response<Body>> for TestResponse {
+    fn from(res: Response<Body>) -> Self {
+        let headers = res
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.as_str().to_owned(), value)
+            })
+            .collect();
+        TestResponse {
+            status: res.status().as_u16(),
+            headers,
+            body: res.body().to_vec(),
+        }
+    }
+}
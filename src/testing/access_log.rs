@@ -0,0 +1,21 @@
+use crate::access_log::{AccessLogEntry, AccessLogSink};
+use std::sync::Mutex;
+
+/// An `AccessLogSink` that records entries in memory, for use in tests.
+#[derive(Default, Debug)]
+pub struct InMemoryAccessLog {
+    entries: Mutex<Vec<AccessLogEntry>>,
+}
+
+impl InMemoryAccessLog {
+    /// Gets all entries that have been logged, in the order they were logged.
+    pub fn entries(&self) -> Vec<AccessLogEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+}
+
+impl AccessLogSink for InMemoryAccessLog {
+    fn log(&self, entry: &AccessLogEntry) {
+        self.entries.lock().unwrap().push(entry.clone());
+    }
+}
@@ -0,0 +1,322 @@
+use super::TestResponse;
+use crate::builder::RocketHandlerBuilder;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const API_PREFIX: &str = "/2018-06-01/runtime/";
+const FUNCTION_ARN: &str = "arn:aws:lambda:eu-west-1:123456789012:function:test-function";
+
+/// The runtime reads its settings from environment variables, which are shared by every thread
+/// of the process, so runtimes are launched one at a time.
+static LAUNCH_LOCK: Mutex<()> = Mutex::new(());
+
+/// The result of an invocation sent through a [MockRuntimeApi].
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvocationResult {
+    /// The runtime returned a response.
+    Response(Value),
+    /// The runtime reported an error, e.g. `{"errorType": "...", "errorMessage": "..."}`.
+    Error(Value),
+    /// The runtime exited (e.g. because the handler panicked) before responding.
+    Exited,
+    /// The runtime did not respond before the function's timeout.
+    TimedOut,
+}
+
+impl InvocationResult {
+    /// Reads the response as an HTTP response, if the runtime returned one.
+    pub fn response(&self) -> Option<TestResponse> {
+        match self {
+            InvocationResult::Response(value) => Some(TestResponse::from_json(value)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    pending: VecDeque<(String, Value)>,
+    results: HashMap<String, InvocationResult>,
+    init_error: Option<Value>,
+    next_id: u64,
+    timeout: Duration,
+    connected: bool,
+    exited: bool,
+}
+
+type SharedState = Arc<(Mutex<State>, Condvar)>;
+
+fn lock(state: &SharedState) -> MutexGuard<State> {
+    state.0.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A local stand-in for the [Lambda Runtime API](https://docs.aws.amazon.com/lambda/latest/dg/runtimes-api.html),
+/// for end-to-end tests of [launch_raw()](crate::RocketHandlerBuilder::launch_raw) or
+/// [launch()](crate::RocketHandlerBuilder::launch) through the real runtime loop.
+///
+/// Each invocation is queued until the runtime polls for the next event, and [invoke](MockRuntimeApi::invoke)
+/// waits for the response or error that the runtime posts back.
+///
+/// # Example
+///
+/// ```rust
+/// use rocket_lamb::testing::{EventBuilder, MockRuntimeApi};
+/// use rocket_lamb::RocketExt;
+///
+/// let api = MockRuntimeApi::start().unwrap();
+/// api.launch(rocket::ignite().lambda());
+///
+/// let result = api.invoke(EventBuilder::rest("GET", "/").to_json());
+/// result.response().unwrap().assert_status(404);
+/// ```
+pub struct MockRuntimeApi {
+    address: String,
+    state: SharedState,
+}
+
+impl MockRuntimeApi {
+    /// Starts the server on a free local port. The function timeout defaults to 3 seconds, as it does in Lambda.
+    pub fn start() -> io::Result<MockRuntimeApi> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?.to_string();
+        let state = SharedState::default();
+        lock(&state).timeout = Duration::from_secs(3);
+
+        let server_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                let state = server_state.clone();
+                thread::spawn(move || {
+                    let _ = serve_connection(stream, &state);
+                });
+            }
+        });
+        Ok(MockRuntimeApi { address, state })
+    }
+
+    /// Gets the address of the server, e.g. `127.0.0.1:9001`, as used for `AWS_LAMBDA_RUNTIME_API`.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Sets the function timeout, which determines the deadline sent to the runtime with each event,
+    /// and how long [invoke](MockRuntimeApi::invoke) waits for the runtime to respond.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        lock(&self.state).timeout = timeout;
+        self
+    }
+
+    /// Launches the Rocket with [launch_raw()](crate::RocketHandlerBuilder::launch_raw) on a new thread, pointing it
+    /// at this server.
+    ///
+    /// This sets `AWS_LAMBDA_RUNTIME_API` and the other environment variables read by the runtime, and returns
+    /// once the runtime has polled for its first event, or has exited.
+    pub fn launch(&self, builder: RocketHandlerBuilder) {
+        self.launch_with(move || builder.launch_raw());
+    }
+
+    /// Launches the Rocket with [launch()](crate::RocketHandlerBuilder::launch) on a new thread, pointing it at
+    /// this server, so that only the API Gateway and Application Load Balancer requests supported by `lambda_http`
+    /// are handled.
+    ///
+    /// This sets the same environment variables as [launch](MockRuntimeApi::launch), and returns once the runtime
+    /// has polled for its first event, or has exited.
+    pub fn launch_http(&self, builder: RocketHandlerBuilder) {
+        self.launch_with(move || builder.launch());
+    }
+
+    fn launch_with(&self, launch: impl FnOnce() + Send + 'static) {
+        let _guard = LAUNCH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        env::set_var("AWS_LAMBDA_RUNTIME_API", &self.address);
+        env::set_var("AWS_LAMBDA_FUNCTION_NAME", "test-function");
+        env::set_var("AWS_LAMBDA_FUNCTION_VERSION", "$LATEST");
+        env::set_var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE", "128");
+        env::set_var("AWS_LAMBDA_LOG_GROUP_NAME", "/aws/lambda/test-function");
+        env::set_var("AWS_LAMBDA_LOG_STREAM_NAME", "test-log-stream");
+
+        let exit_guard = ExitGuard(self.state.clone());
+        thread::spawn(move || {
+            let _exit_guard = exit_guard;
+            launch();
+        });
+
+        let mut state = lock(&self.state);
+        while !state.connected && !state.exited {
+            state = self.state.1.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Sends an event to the runtime and waits for the result of the invocation.
+    pub fn invoke(&self, event: Value) -> InvocationResult {
+        let mut state = lock(&self.state);
+        state.next_id += 1;
+        let request_id = format!("mock-request-{}", state.next_id);
+        state.pending.push_back((request_id.clone(), event));
+        self.state.1.notify_all();
+
+        let deadline = Instant::now() + state.timeout;
+        loop {
+            if let Some(result) = state.results.remove(&request_id) {
+                return result;
+            }
+            if state.exited {
+                return InvocationResult::Exited;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return InvocationResult::TimedOut;
+            }
+            state = self
+                .state
+                .1
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    /// Gets the error reported by the runtime to the init error endpoint, if any.
+    pub fn init_error(&self) -> Option<Value> {
+        lock(&self.state).init_error.clone()
+    }
+
+    /// Determines whether the runtime has exited.
+    pub fn has_exited(&self) -> bool {
+        lock(&self.state).exited
+    }
+}
+
+/// Marks the runtime as exited when its thread finishes, including when it panics.
+struct ExitGuard(SharedState);
+
+impl Drop for ExitGuard {
+    fn drop(&mut self) {
+        lock(&self.0).exited = true;
+        self.0 .1.notify_all();
+    }
+}
+
+fn serve_connection(stream: TcpStream, state: &SharedState) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line)? == 0 {
+            return Ok(());
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_owned();
+        let path = parts.next().unwrap_or_default().to_owned();
+
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let mut header = line.splitn(2, ':');
+            let name = header.next().unwrap_or_default();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = header
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .parse()
+                    .unwrap_or(0);
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let route = path.strip_prefix(API_PREFIX).unwrap_or_default();
+        let segments: Vec<&str> = route.split('/').collect();
+        match (method.as_str(), segments.as_slice()) {
+            ("GET", ["invocation", "next"]) => {
+                let (request_id, event, timeout) = next_event(state);
+                let deadline = SystemTime::now() + timeout;
+                let deadline_ms = deadline
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis())
+                    .unwrap_or_default();
+                let headers = [
+                    ("Lambda-Runtime-Aws-Request-Id", request_id),
+                    ("Lambda-Runtime-Deadline-Ms", deadline_ms.to_string()),
+                    (
+                        "Lambda-Runtime-Invoked-Function-Arn",
+                        FUNCTION_ARN.to_owned(),
+                    ),
+                    ("Content-Type", "application/json".to_owned()),
+                ];
+                write_response(
+                    &mut writer,
+                    "200 OK",
+                    &headers,
+                    event.to_string().as_bytes(),
+                )?;
+            }
+            ("POST", ["invocation", request_id, kind @ "response"])
+            | ("POST", ["invocation", request_id, kind @ "error"]) => {
+                let value = parse_body(&body);
+                let result = if *kind == "response" {
+                    InvocationResult::Response(value)
+                } else {
+                    InvocationResult::Error(value)
+                };
+                lock(state).results.insert((*request_id).to_owned(), result);
+                state.1.notify_all();
+                write_response(&mut writer, "202 Accepted", &[], b"")?;
+            }
+            ("POST", ["init", "error"]) => {
+                lock(state).init_error = Some(parse_body(&body));
+                state.1.notify_all();
+                write_response(&mut writer, "202 Accepted", &[], b"")?;
+            }
+            _ => write_response(&mut writer, "404 Not Found", &[], b"")?,
+        }
+    }
+}
+
+/// Waits for an invocation to be queued, returning it along with the function timeout.
+/// The first call also marks the runtime as connected.
+fn next_event(state: &SharedState) -> (String, Value, Duration) {
+    let mut guard = lock(state);
+    guard.connected = true;
+    state.1.notify_all();
+    loop {
+        if let Some((request_id, event)) = guard.pending.pop_front() {
+            return (request_id, event, guard.timeout);
+        }
+        guard = state.1.wait(guard).unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+fn parse_body(body: &[u8]) -> Value {
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+fn write_response(
+    writer: &mut impl Write,
+    status: &str,
+    headers: &[(&str, String)],
+    body: &[u8],
+) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\n", status, body.len());
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes())?;
+    writer.write_all(body)?;
+    writer.flush()
+}
@@ -0,0 +1,258 @@
+use crate::encoding::{encode_query_component, push_query_pair};
+use crate::error::RocketLambError;
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WebSocketEvent {
+    request_context: WebSocketRequestContext,
+    #[serde(default)]
+    headers: Option<HashMap<String, String>>,
+    #[serde(default)]
+    query_string_parameters: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    multi_value_query_string_parameters: Option<BTreeMap<String, Vec<String>>>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    is_base64_encoded: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebSocketRequestContext {
+    route_key: String,
+    #[serde(default)]
+    event_type: Option<String>,
+    connection_id: String,
+    #[serde(default)]
+    domain_name: String,
+    #[serde(default)]
+    stage: String,
+}
+
+/// A WebSocket message converted into the parts needed to build a Rocket request.
+pub(crate) struct WebSocketRequest {
+    pub(crate) connection: WebSocketConnection,
+    pub(crate) uri: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl WebSocketEvent {
+    /// Maps the event onto a Rocket request. The route key becomes the path (e.g. `$connect`
+    /// becomes `/$connect`), and the message body becomes the request body.
+    pub(crate) fn into_request(self) -> Result<WebSocketRequest, RocketLambError> {
+        let context = self.request_context;
+        let mut uri = route_key_to_path(&context.route_key);
+        // As with REST API requests, keys are sorted by name and each key's values keep their order.
+        let params = match (
+            self.multi_value_query_string_parameters,
+            self.query_string_parameters,
+        ) {
+            (Some(params), _) => params,
+            (None, Some(params)) => params.into_iter().map(|(k, v)| (k, vec![v])).collect(),
+            (None, None) => BTreeMap::new(),
+        };
+        let mut query = String::new();
+        for (key, values) in params {
+            for value in values {
+                let value = Some(value)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| encode_query_component(&v));
+                push_query_pair(
+                    &mut query,
+                    &encode_query_component(&key),
+                    value.as_ref().map(String::as_str),
+                );
+            }
+        }
+        if !query.is_empty() {
+            uri.push('?');
+            uri.push_str(&query);
+        }
+
+        let body = match self.body {
+            Some(b) if self.is_base64_encoded => base64::decode(&b)
+                .map_err(|e| invalid_request!("invalid base-64 message body: {}", e))?,
+            Some(b) => b.into_bytes(),
+            None => Vec::new(),
+        };
+
+        let event_type = context
+            .event_type
+            .unwrap_or_else(|| match context.route_key.as_str() {
+                "$connect" => "CONNECT".to_owned(),
+                "$disconnect" => "DISCONNECT".to_owned(),
+                _ => "MESSAGE".to_owned(),
+            });
+
+        Ok(WebSocketRequest {
+            connection: WebSocketConnection {
+                connection_id: context.connection_id,
+                domain_name: context.domain_name,
+                stage: context.stage,
+                route_key: context.route_key,
+                event_type,
+            },
+            uri,
+            headers: self.headers.unwrap_or_default().into_iter().collect(),
+            body,
+        })
+    }
+}
+
+fn route_key_to_path(route_key: &str) -> String {
+    let mut path = String::from("/");
+    for b in route_key.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'$' => {
+                path.push(b as char)
+            }
+            _ => path.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    path
+}
+
+/// Request guard for the API Gateway WebSocket connection that sent the current message.
+///
+/// WebSocket events are dispatched to Rocket as `POST` requests to a path derived from the
+/// route key, so `$connect`, `$disconnect` and `$default` are handled by routes mounted at
+/// `/$connect`, `/$disconnect` and `/$default`, and a custom route key such as `sendMessage`
+/// is handled by a route mounted at `/sendMessage`. The route's response body is sent back
+/// to the client for routes configured with two-way communication.
+///
+/// This guard forwards if the request did not come from a WebSocket API.
+///
+/// # Example
+///
+/// ```rust
+/// #![feature(proc_macro_hygiene, decl_macro)]
+/// # #[macro_use] extern crate rocket;
+/// use rocket_lamb::WebSocketConnection;
+///
+/// #[post("/$connect")]
+/// fn connect(connection: WebSocketConnection) -> String {
+///     format!("Hello, {}!", connection.connection_id)
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebSocketConnection {
+    /// The unique ID of the connection, used to send messages back to the client.
+    pub connection_id: String,
+    /// The domain name of the WebSocket API, e.g. `{api-id}.execute-api.{region}.amazonaws.com`.
+    pub domain_name: String,
+    /// The API Gateway stage that received the message.
+    pub stage: String,
+    /// The route key that was selected for the message, e.g. `$connect` or `sendMessage`.
+    pub route_key: String,
+    /// The type of event: `CONNECT`, `DISCONNECT` or `MESSAGE`.
+    pub event_type: String,
+}
+
+impl WebSocketConnection {
+    /// Gets the base URL of the API Gateway Management API for this connection's stage.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::WebSocketConnection;
+    ///
+    /// let connection = WebSocketConnection {
+    ///     connection_id: "abc=".to_owned(),
+    ///     domain_name: "example.com".to_owned(),
+    ///     stage: "Prod".to_owned(),
+    ///     route_key: "$default".to_owned(),
+    ///     event_type: "MESSAGE".to_owned(),
+    /// };
+    /// assert_eq!(connection.endpoint(), "https://example.com/Prod");
+    /// ```
+    pub fn endpoint(&self) -> String {
+        format!("https://{}/{}", self.domain_name, self.stage)
+    }
+
+    /// Sends `data` to this connection using the given `ConnectionClient`.
+    pub fn post<C: ConnectionClient + ?Sized>(
+        &self,
+        client: &C,
+        data: &[u8],
+    ) -> Result<(), failure::Error> {
+        client.post_to_connection(&self.endpoint(), &self.connection_id, data)
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for WebSocketConnection {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match request.local_cache(|| None::<WebSocketConnection>) {
+            Some(connection) => Outcome::Success(connection.clone()),
+            None => Outcome::Forward(()),
+        }
+    }
+}
+
+/// A client that can send messages to connected WebSocket clients, typically by calling the
+/// API Gateway Management API's `PostToConnection` operation.
+///
+/// Implementations are usually added to the `Rocket` as managed state, so that routes can
+/// retrieve them with `State`.
+pub trait ConnectionClient: Send + Sync {
+    /// Sends `data` to the connection with the given ID, using the Management API at `endpoint`.
+    fn post_to_connection(
+        &self,
+        endpoint: &str,
+        connection_id: &str,
+        data: &[u8],
+    ) -> Result<(), failure::Error>;
+}
+
+/// A `ConnectionClient` that records messages in memory instead of sending them, for use in tests.
+///
+/// # Example
+///
+/// ```rust
+/// use rocket_lamb::{ConnectionClient, InMemoryConnectionClient};
+///
+/// let client = InMemoryConnectionClient::default();
+/// client.post_to_connection("https://example.com/Prod", "abc=", b"hello").unwrap();
+/// assert_eq!(client.messages("abc="), vec![b"hello".to_vec()]);
+/// ```
+#[derive(Default, Debug)]
+pub struct InMemoryConnectionClient {
+    messages: Mutex<Vec<(String, Vec<u8>)>>,
+}
+
+impl InMemoryConnectionClient {
+    /// Gets all messages that have been sent to the connection with the given ID, in the order they were sent.
+    pub fn messages(&self, connection_id: &str) -> Vec<Vec<u8>> {
+        self.messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _)| id == connection_id)
+            .map(|(_, data)| data.clone())
+            .collect()
+    }
+}
+
+impl ConnectionClient for InMemoryConnectionClient {
+    fn post_to_connection(
+        &self,
+        _endpoint: &str,
+        connection_id: &str,
+        data: &[u8],
+    ) -> Result<(), failure::Error> {
+        self.messages
+            .lock()
+            .unwrap()
+            .push((connection_id.to_owned(), data.to_vec()));
+        Ok(())
+    }
+}
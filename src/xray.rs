@@ -0,0 +1,227 @@
+use crate::route::MatchedRoute;
+use lambda_http::{Body, Response};
+use rocket::request::{self, FromRequest};
+use rocket::{Outcome, Request};
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::hash::{BuildHasher, Hasher};
+use std::net::UdpSocket;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The address of the X-Ray daemon when `AWS_XRAY_DAEMON_ADDRESS` is not set.
+const DEFAULT_DAEMON_ADDRESS: &str = "127.0.0.1:2000";
+
+/// Request guard for the AWS X-Ray trace context of a request, parsed from the trace ID that
+/// Lambda passes to the invocation, or the `X-Amzn-Trace-Id` request header.
+///
+/// This guard forwards if the request is not being traced.
+///
+/// # Example
+///
+/// ```rust
+/// #![feature(proc_macro_hygiene, decl_macro)]
+/// # #[macro_use] extern crate rocket;
+/// use rocket_lamb::TraceContext;
+///
+/// #[get("/trace")]
+/// fn trace(trace: TraceContext) -> String {
+///     trace.root
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    /// The trace ID, e.g. `1-5759e988-bd862e3fe1be46a994272793`.
+    pub root: String,
+    /// The ID of the parent segment, if there is one.
+    pub parent: Option<String>,
+    /// Whether the trace is sampled, if the sampling decision has been made.
+    pub sampled: Option<bool>,
+}
+
+impl TraceContext {
+    /// Parses a trace header, e.g.
+    /// `Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1`.
+    /// Returns `None` if the header has no `Root`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rocket_lamb::TraceContext;
+    ///
+    /// let header = "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1";
+    /// let trace = TraceContext::parse(header).unwrap();
+    /// assert_eq!(trace.root, "1-5759e988-bd862e3fe1be46a994272793");
+    /// assert_eq!(trace.parent, Some("53995c3f42cd8ad8".to_owned()));
+    /// assert_eq!(trace.sampled, Some(true));
+    /// assert_eq!(trace.to_header(), header);
+    /// ```
+    pub fn parse(header: &str) -> Option<TraceContext> {
+        let mut root = None;
+        let mut parent = None;
+        let mut sampled = None;
+        for part in header.split(';') {
+            let mut kv = part.trim().splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some("Root"), Some(v)) => root = Some(v.to_owned()),
+                (Some("Parent"), Some(v)) => parent = Some(v.to_owned()),
+                (Some("Sampled"), Some("1")) => sampled = Some(true),
+                (Some("Sampled"), Some("0")) => sampled = Some(false),
+                _ => {}
+            }
+        }
+        root.map(|root| TraceContext {
+            root,
+            parent,
+            sampled,
+        })
+    }
+
+    /// Formats the trace context as an `X-Amzn-Trace-Id` header value.
+    pub fn to_header(&self) -> String {
+        let mut header = format!("Root={}", self.root);
+        if let Some(parent) = &self.parent {
+            header.push_str(";Parent=");
+            header.push_str(parent);
+        }
+        match self.sampled {
+            Some(true) => header.push_str(";Sampled=1"),
+            Some(false) => header.push_str(";Sampled=0"),
+            None => {}
+        }
+        header
+    }
+
+    /// Gets the trace context of an invocation from the trace ID that Lambda passed to it, the
+    /// `_X_AMZN_TRACE_ID` environment variable, or the request's `X-Amzn-Trace-Id` header, in that order.
+    pub(crate) fn find(
+        lambda_trace_id: Option<&str>,
+        header: Option<&str>,
+    ) -> Option<TraceContext> {
+        lambda_trace_id
+            .and_then(TraceContext::parse)
+            .or_else(|| {
+                env::var("_X_AMZN_TRACE_ID")
+                    .ok()
+                    .and_then(|v| TraceContext::parse(&v))
+            })
+            .or_else(|| header.and_then(TraceContext::parse))
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for TraceContext {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match request.local_cache(|| None::<TraceContext>) {
+            Some(trace) => Outcome::Success(trace.clone()),
+            None => Outcome::Forward(()),
+        }
+    }
+}
+
+/// Sends a segment to the X-Ray daemon for each request.
+pub(crate) struct XRayEmitter {
+    daemon_address: String,
+    /// The address passed to `new`, if any.
+    configured_address: Option<String>,
+}
+
+impl XRayEmitter {
+    /// Creates an emitter that sends segments to `daemon_address`, or if that is `None`, to the
+    /// address in `AWS_XRAY_DAEMON_ADDRESS`.
+    pub(crate) fn new(daemon_address: Option<&str>) -> XRayEmitter {
+        let configured_address = daemon_address.map(str::to_owned);
+        let daemon_address = match daemon_address {
+            Some(address) => address.to_owned(),
+            None => env::var("AWS_XRAY_DAEMON_ADDRESS")
+                .map(|v| udp_address(&v))
+                .unwrap_or_else(|_| DEFAULT_DAEMON_ADDRESS.to_owned()),
+        };
+        XRayEmitter {
+            daemon_address,
+            configured_address,
+        }
+    }
+
+    /// Gets the address passed to `new`, or `None` if the address is read from the environment.
+    pub(crate) fn configured_address(&self) -> Option<&str> {
+        self.configured_address.as_deref()
+    }
+
+    /// Sends a subsegment of the trace's parent segment for a request (or a segment if it has
+    /// no parent), unless the trace is not sampled.
+    pub(crate) fn emit(
+        &self,
+        trace: &TraceContext,
+        method: &str,
+        url: &str,
+        start_time: SystemTime,
+        res: &Response<Body>,
+    ) {
+        if trace.sampled == Some(false) {
+            return;
+        }
+        let route = res.extensions().get::<MatchedRoute>();
+        let status = res.status().as_u16();
+        let mut segment = json!({
+            "name": route.and_then(|r| r.name).unwrap_or("rocket"),
+            "id": new_id(),
+            "trace_id": trace.root,
+            "start_time": epoch_secs(start_time),
+            "end_time": epoch_secs(SystemTime::now()),
+            "http": {
+                "request": { "method": method, "url": url },
+                "response": { "status": status, "content_length": res.body().len() },
+            },
+            "annotations": {
+                "route": route.map(MatchedRoute::id).unwrap_or_else(|| "unmatched".to_owned()),
+            },
+            "error": (400..500).contains(&status),
+            "throttle": status == 429,
+            "fault": status >= 500,
+        });
+        if let Some(parent) = &trace.parent {
+            segment["type"] = Value::String("subsegment".to_owned());
+            segment["parent_id"] = Value::String(parent.clone());
+            segment["namespace"] = Value::String("local".to_owned());
+        }
+
+        let packet = format!("{{\"format\": \"json\", \"version\": 1}}\n{}", segment);
+        // Tracing is best-effort, so a failure to reach the daemon must not fail the request.
+        if let Ok(socket) = UdpSocket::bind("0.0.0.0:0") {
+            let _ = socket.send_to(packet.as_bytes(), self.daemon_address.as_str());
+        }
+    }
+}
+
+/// Gets the UDP address from an `AWS_XRAY_DAEMON_ADDRESS` value, which is either a single address
+/// or separate TCP and UDP addresses, e.g. `tcp:127.0.0.1:2000 udp:127.0.0.2:2001`.
+fn udp_address(value: &str) -> String {
+    value
+        .split(' ')
+        .find(|a| a.starts_with("udp:"))
+        .map(|a| &a[4..])
+        .unwrap_or(value)
+        .to_owned()
+}
+
+/// Generates a random, non-zero 64-bit ID, formatted as 16 hexadecimal digits. This is used for
+/// X-Ray segment IDs and OpenTelemetry span IDs.
+pub(crate) fn new_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default(),
+    );
+    format!("{:016x}", hasher.finish().max(1))
+}
+
+fn epoch_secs(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default()
+}
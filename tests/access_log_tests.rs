@@ -0,0 +1,99 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_http::{Body, Handler, Request, Response};
+use lambda_runtime::Context;
+use rocket_lamb::testing::InMemoryAccessLog;
+use rocket_lamb::{ResponseType, RocketExt};
+use std::error::Error;
+use std::fs::File;
+use std::sync::Arc;
+
+#[post("/upper/<path>")]
+fn upper(path: String) -> String {
+    path.to_uppercase()
+}
+
+fn get_request(json_file: &str) -> Result<Request, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(lambda_http::request::from_reader(file)?)
+}
+
+#[test]
+fn logs_each_request() -> Result<(), Box<dyn Error>> {
+    let log = Arc::new(InMemoryAccessLog::default());
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .access_log(log.clone())
+        .into_handler();
+
+    let mut ctx = Context::default();
+    ctx.aws_request_id = "request-1".to_owned();
+    handler.run(get_request("upper")?, ctx.clone())?;
+    handler.run(get_request("upper")?, ctx)?;
+
+    let entries = log.entries();
+    assert_eq!(entries.len(), 2);
+    let entry = &entries[0];
+    assert_eq!(entry.request_id, "request-1");
+    assert_eq!(entry.remote_addr, Some("1.2.3.4".to_owned()));
+    assert_eq!(entry.method, "POST");
+    assert_eq!(entry.path, "/upper/one?query=two");
+    assert_eq!(entry.original_path, "/upper/one");
+    assert_eq!(entry.base_path, "");
+    assert_eq!(entry.status, 200);
+    assert_eq!(entry.response_type, Some(ResponseType::Text));
+    assert_eq!(entry.request_body_size, 5);
+    assert_eq!(entry.response_body_size, 3);
+    assert!(entry.cold_start);
+    assert!(!entries[1].cold_start);
+    Ok(())
+}
+
+#[test]
+fn redacts_headers_and_query_parameters() -> Result<(), Box<dyn Error>> {
+    let log = Arc::new(InMemoryAccessLog::default());
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .access_log(log.clone())
+        .redact_header("X-FORWARDED-FOR")
+        .redact_query_parameter("query")
+        .into_handler();
+
+    handler.run(get_request("upper")?, Context::default())?;
+
+    let entry = &log.entries()[0];
+    assert_eq!(entry.path, "/upper/one?query=REDACTED");
+    let forwarded_for = entry
+        .headers
+        .iter()
+        .find(|(name, _)| name == "x-forwarded-for")
+        .map(|(_, value)| value.as_str());
+    assert_eq!(forwarded_for, Some("REDACTED"));
+    Ok(())
+}
+
+#[test]
+fn logs_requests_with_unknown_base_path() -> Result<(), Box<dyn Error>> {
+    let log = Arc::new(InMemoryAccessLog::default());
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .access_log(log.clone())
+        .before_request(|_, _| Some(Response::builder().status(204).body(Body::Empty).unwrap()))
+        .into_handler();
+
+    let res = handler.run(get_request("path_missing_parameter")?, Context::default())?;
+
+    assert_eq!(res.status(), 204);
+    let entry = &log.entries()[0];
+    assert_eq!(entry.path, "/base-path/path/");
+    assert_eq!(entry.original_path, "/base-path/path/");
+    assert_eq!(entry.base_path, "");
+    assert_eq!(entry.status, 204);
+    Ok(())
+}
@@ -0,0 +1,77 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_runtime::{Context, Handler};
+use rocket::response::Response;
+use rocket_lamb::{AlbHealthCheck, RocketExt};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs::File;
+use std::io::Cursor;
+
+#[get("/query?<name>")]
+fn query<'r>(name: String) -> Response<'r> {
+    Response::build()
+        .raw_header_adjoin("X-Tag", "one")
+        .raw_header_adjoin("X-Tag", "two")
+        .sized_body(Cursor::new(name))
+        .finalize()
+}
+
+#[get("/query", rank = 1)]
+fn health_check(_health_check: AlbHealthCheck) -> &'static str {
+    "healthy"
+}
+
+fn make_rocket() -> rocket::Rocket {
+    rocket::ignite().mount("/", routes![query, health_check])
+}
+
+fn get_event(json_file: &str) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+#[test]
+fn alb_single_value_headers() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let event = get_event("path_alb")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 404);
+    assert_eq!(res["statusDescription"], "404 Not Found");
+    assert!(res["headers"].is_object(), "Response should have headers");
+    assert_eq!(res.get("multiValueHeaders"), None);
+    Ok(())
+}
+
+#[test]
+fn alb_multi_value_headers() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let event = get_event("alb_multi_value")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["statusDescription"], "200 OK");
+    assert_eq!(res["multiValueHeaders"]["x-tag"], json!(["one", "two"]));
+    assert_eq!(res.get("headers"), None);
+    assert_eq!(res["body"], "hello world");
+    Ok(())
+}
+
+#[test]
+fn alb_health_check() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let event = get_event("alb_health_check")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["headers"]["content-type"], "text/plain; charset=utf-8");
+    assert_eq!(res["body"], "healthy");
+    Ok(())
+}
@@ -0,0 +1,108 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use rocket_lamb::{RocketExt, RocketHandlerBuilder};
+use serde_json::{Deserializer, Value};
+
+#[post("/upper/<path>", data = "<body>")]
+fn upper(path: String, body: String) -> String {
+    format!("{} {}", path, body).to_uppercase()
+}
+
+#[get("/path")]
+fn get_path() -> &'static str {
+    "path"
+}
+
+fn make_builder() -> RocketHandlerBuilder {
+    rocket::ignite()
+        .mount("/", routes![upper, get_path])
+        .lambda()
+}
+
+fn run_cli(args: &[&str], input: &str) -> (i32, String) {
+    let mut output = Vec::new();
+    let exit_code =
+        make_builder().run_cli_with_args(args.to_vec(), &mut input.as_bytes(), &mut output);
+    (exit_code, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn invoke_file_as_json() {
+    let (exit_code, output) = run_cli(&["invoke", "tests/requests/upper.json"], "");
+    assert_eq!(exit_code, 0);
+
+    let res: Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["body"], "ONE THREE");
+}
+
+#[test]
+fn invoke_files_as_http() {
+    let (exit_code, output) = run_cli(
+        &[
+            "--http",
+            "tests/requests/upper.json",
+            "tests/requests/not_found.json",
+        ],
+        "",
+    );
+    assert_eq!(exit_code, 0);
+
+    let responses: Vec<&str> = output.split("HTTP/1.1 ").skip(1).collect();
+    assert_eq!(responses.len(), 2);
+    assert!(responses[0].starts_with("200 OK\n"), "{}", responses[0]);
+    assert!(responses[0].contains("content-type: text/plain; charset=utf-8\n"));
+    assert!(
+        responses[0].ends_with("\n\nONE THREE\n"),
+        "{}",
+        responses[0]
+    );
+    assert!(
+        responses[1].starts_with("404 Not Found\n"),
+        "{}",
+        responses[1]
+    );
+}
+
+#[test]
+fn invoke_events_from_stdin() {
+    let upper = std::fs::read_to_string("tests/requests/upper.json").unwrap();
+    let alb = std::fs::read_to_string("tests/requests/path_alb.json").unwrap();
+    let (exit_code, output) = run_cli(&[], &format!("{}\n{}", upper, alb));
+    assert_eq!(exit_code, 0);
+
+    let responses: Vec<Value> = Deserializer::from_str(&output)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["body"], "ONE THREE");
+    assert_eq!(responses[1]["statusCode"], 200);
+    assert_eq!(responses[1]["statusDescription"], "200 OK");
+}
+
+#[test]
+fn failed_invocation() {
+    let (exit_code, output) = run_cli(&["-"], r#"{"hello": "world"}"#);
+    assert_eq!(exit_code, 1);
+
+    let error: Value = serde_json::from_str(&output).unwrap();
+    assert!(error["errorMessage"]
+        .as_str()
+        .unwrap()
+        .contains("no direct invocation route is configured"));
+}
+
+#[test]
+fn invalid_arguments() {
+    assert_eq!(run_cli(&["--unknown"], "").0, 2);
+    assert_eq!(run_cli(&["tests/requests/missing.json"], "").0, 2);
+    assert_eq!(run_cli(&["-"], "{ not json").0, 2);
+
+    let (exit_code, output) = run_cli(&["--help"], "");
+    assert_eq!(exit_code, 0);
+    assert!(output.contains("USAGE:"));
+}
@@ -0,0 +1,392 @@
+use rocket::config::{Config, Environment, Value};
+use rocket::http::Method;
+use rocket_lamb::{
+    BasePathBehaviour, ConfigError, ExtensionMethodBehaviour, PathNormalization,
+    QueryReconstruction, ResponseType, RocketExt, WatchdogConfig,
+};
+use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Environment variables are shared by every test in this file, so they are set one test at a time.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+const VARS: [&str; 9] = [
+    "ROCKET_LAMB_DEFAULT_RESPONSE_TYPE",
+    "ROCKET_LAMB_RESPONSE_TYPES",
+    "ROCKET_LAMB_BASE_PATH_BEHAVIOUR",
+    "ROCKET_LAMB_QUERY_RECONSTRUCTION",
+    "ROCKET_LAMB_PATH_NORMALIZATION",
+    "ROCKET_LAMB_EXTENSION_METHODS",
+    "ROCKET_LAMB_DIRECT_INVOCATION_ROUTE",
+    "ROCKET_LAMB_DEADLINE_WATCHDOG",
+    "ROCKET_LAMB_REDACTED_HEADERS",
+];
+
+fn with_env<T>(vars: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+    let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    for var in VARS.iter() {
+        env::remove_var(var);
+    }
+    for (name, value) in vars {
+        env::set_var(name, value);
+    }
+    let result = f();
+    for (name, _) in vars {
+        env::remove_var(name);
+    }
+    result
+}
+
+fn make_rocket(lambda_table: Option<&str>) -> rocket::Rocket {
+    let mut config = Config::build(Environment::Production);
+    if let Some(table) = lambda_table {
+        config = config.extra("lambda", table.parse::<Value>().unwrap());
+    }
+    rocket::custom(config.finalize().unwrap())
+}
+
+#[test]
+fn no_settings_keeps_defaults() {
+    let builder = with_env(&[], || make_rocket(None).lambda().load_config().unwrap());
+
+    assert_eq!(builder.get_default_response_type(), ResponseType::Auto);
+    assert_eq!(builder.get_response_type("image/png"), ResponseType::Auto);
+    assert_eq!(
+        builder.get_base_path_behaviour(),
+        &BasePathBehaviour::RemountAndInclude
+    );
+}
+
+#[test]
+fn settings_from_rocket_config() {
+    let table = r#"
+        default_response_type = "text"
+        base_path_behaviour = "exclude"
+
+        [response_types]
+        "IMAGE/PNG" = "binary"
+        "application/pdf" = "Binary"
+    "#;
+    let builder = with_env(&[], || {
+        make_rocket(Some(table)).lambda().load_config().unwrap()
+    });
+
+    assert_eq!(builder.get_default_response_type(), ResponseType::Text);
+    assert_eq!(builder.get_response_type("image/png"), ResponseType::Binary);
+    assert_eq!(
+        builder.get_response_type("application/pdf"),
+        ResponseType::Binary
+    );
+    assert_eq!(
+        builder.get_base_path_behaviour(),
+        &BasePathBehaviour::Exclude
+    );
+}
+
+#[test]
+fn settings_from_env() {
+    let vars = [
+        ("ROCKET_LAMB_DEFAULT_RESPONSE_TYPE", "binary"),
+        (
+            "ROCKET_LAMB_RESPONSE_TYPES",
+            "text/plain=text; application/json = auto",
+        ),
+        ("ROCKET_LAMB_BASE_PATH_BEHAVIOUR", "include"),
+    ];
+    let builder = with_env(&vars, || make_rocket(None).lambda().load_config().unwrap());
+
+    assert_eq!(builder.get_default_response_type(), ResponseType::Binary);
+    assert_eq!(builder.get_response_type("text/plain"), ResponseType::Text);
+    assert_eq!(
+        builder.get_response_type("application/json"),
+        ResponseType::Auto
+    );
+    assert_eq!(
+        builder.get_base_path_behaviour(),
+        &BasePathBehaviour::Include
+    );
+}
+
+#[test]
+fn env_takes_precedence_over_rocket_config() {
+    let table = r#"
+        default_response_type = "text"
+
+        [response_types]
+        "image/png" = "text"
+        "text/csv" = "text"
+    "#;
+    let vars = [
+        ("ROCKET_LAMB_DEFAULT_RESPONSE_TYPE", "binary"),
+        ("ROCKET_LAMB_RESPONSE_TYPES", "image/png=binary"),
+    ];
+    let builder = with_env(&vars, || {
+        make_rocket(Some(table)).lambda().load_config().unwrap()
+    });
+
+    assert_eq!(builder.get_default_response_type(), ResponseType::Binary);
+    assert_eq!(builder.get_response_type("image/png"), ResponseType::Binary);
+    assert_eq!(builder.get_response_type("text/csv"), ResponseType::Text);
+}
+
+#[test]
+fn code_takes_precedence_over_loaded_settings() {
+    let table = r#"
+        default_response_type = "text"
+        base_path_behaviour = "exclude"
+
+        [response_types]
+        "image/png" = "text"
+        "text/csv" = "text"
+    "#;
+    let vars = [("ROCKET_LAMB_RESPONSE_TYPES", "image/gif=text")];
+    let builder = with_env(&vars, || {
+        make_rocket(Some(table))
+            .lambda()
+            .default_response_type(ResponseType::Auto)
+            .base_path_behaviour(BasePathBehaviour::Include)
+            .response_type("image/png", ResponseType::Binary)
+            .load_config()
+            .unwrap()
+            .response_type("image/gif", ResponseType::Binary)
+    });
+
+    assert_eq!(builder.get_default_response_type(), ResponseType::Auto);
+    assert_eq!(
+        builder.get_base_path_behaviour(),
+        &BasePathBehaviour::Include
+    );
+    assert_eq!(builder.get_response_type("image/png"), ResponseType::Binary);
+    assert_eq!(builder.get_response_type("image/gif"), ResponseType::Binary);
+    assert_eq!(builder.get_response_type("text/csv"), ResponseType::Text);
+}
+
+#[test]
+fn other_settings_from_rocket_config() {
+    let table = r#"
+        query_reconstruction = "unordered"
+        path_normalization = "collapse_slashes"
+        direct_invocation_route = "/invoke"
+        edge_continue_status = 304
+        record_bodies = true
+        redacted_headers = ["x-api-key"]
+        deadline_watchdog = { margin_ms = 500, status = 504 }
+
+        [extension_methods]
+        PURGE = { map_to = "DELETE" }
+        propfind = { fallback = "/fallback" }
+    "#;
+    let builder = with_env(&[], || {
+        make_rocket(Some(table)).lambda().load_config().unwrap()
+    });
+    let config = builder.get_config();
+
+    assert_eq!(config.query_reconstruction, QueryReconstruction::Unordered);
+    assert_eq!(
+        config.path_normalization,
+        PathNormalization::CollapseSlashes
+    );
+    assert_eq!(config.direct_invocation_route.as_deref(), Some("/invoke"));
+    assert_eq!(config.edge_continue_status, Some(304));
+    assert!(config.record_bodies);
+    assert!(config
+        .redacted_headers
+        .contains(&"authorization".to_owned()));
+    assert!(config.redacted_headers.contains(&"x-api-key".to_owned()));
+    assert_eq!(
+        config.deadline_watchdog,
+        Some(WatchdogConfig {
+            margin_ms: 500,
+            status: 504
+        })
+    );
+    assert_eq!(
+        builder.get_extension_method("PURGE"),
+        &ExtensionMethodBehaviour::MapTo(Method::Delete)
+    );
+    assert_eq!(
+        builder.get_extension_method("PROPFIND"),
+        &ExtensionMethodBehaviour::Fallback("/fallback".to_owned())
+    );
+}
+
+#[test]
+fn other_settings_from_env() {
+    let vars = [
+        ("ROCKET_LAMB_QUERY_RECONSTRUCTION", "unordered"),
+        ("ROCKET_LAMB_PATH_NORMALIZATION", "\"redirect\""),
+        ("ROCKET_LAMB_DIRECT_INVOCATION_ROUTE", "/invoke"),
+        (
+            "ROCKET_LAMB_DEADLINE_WATCHDOG",
+            "{ margin_ms = 500, status = 504 }",
+        ),
+        (
+            "ROCKET_LAMB_EXTENSION_METHODS",
+            "{ PURGE = { map_to = \"DELETE\" } }",
+        ),
+    ];
+    let builder = with_env(&vars, || make_rocket(None).lambda().load_config().unwrap());
+    let config = builder.get_config();
+
+    assert_eq!(config.query_reconstruction, QueryReconstruction::Unordered);
+    assert_eq!(config.path_normalization, PathNormalization::Redirect);
+    assert_eq!(config.direct_invocation_route.as_deref(), Some("/invoke"));
+    assert_eq!(
+        config.deadline_watchdog,
+        Some(WatchdogConfig {
+            margin_ms: 500,
+            status: 504
+        })
+    );
+    assert_eq!(
+        builder.get_extension_method("purge"),
+        &ExtensionMethodBehaviour::MapTo(Method::Delete)
+    );
+}
+
+#[test]
+fn code_takes_precedence_over_other_loaded_settings() {
+    let table = r#"
+        query_reconstruction = "unordered"
+        redacted_headers = ["x-api-key"]
+        deadline_watchdog = { margin_ms = 500, status = 504 }
+
+        [extension_methods]
+        PURGE = "reject"
+        PROPFIND = { map_to = "GET" }
+    "#;
+    let vars = [(
+        "ROCKET_LAMB_DEADLINE_WATCHDOG",
+        "{ margin_ms = 250, status = 503 }",
+    )];
+    let builder = with_env(&vars, || {
+        make_rocket(Some(table))
+            .lambda()
+            .query_reconstruction(QueryReconstruction::Ordered)
+            .extension_method("purge", ExtensionMethodBehaviour::MapTo(Method::Delete))
+            .redact_header("x-session")
+            .load_config()
+            .unwrap()
+    });
+    let config = builder.get_config();
+
+    assert_eq!(config.query_reconstruction, QueryReconstruction::Ordered);
+    assert_eq!(
+        config.deadline_watchdog,
+        Some(WatchdogConfig {
+            margin_ms: 250,
+            status: 503
+        })
+    );
+    assert!(config.redacted_headers.contains(&"x-session".to_owned()));
+    assert!(config.redacted_headers.contains(&"x-api-key".to_owned()));
+    assert_eq!(
+        builder.get_extension_method("PURGE"),
+        &ExtensionMethodBehaviour::MapTo(Method::Delete)
+    );
+    assert_eq!(
+        builder.get_extension_method("PROPFIND"),
+        &ExtensionMethodBehaviour::MapTo(Method::Get)
+    );
+
+    let builder = with_env(&[], || {
+        make_rocket(Some(table))
+            .lambda()
+            .deadline_watchdog(Duration::from_millis(100), 502)
+            .load_config()
+            .unwrap()
+    });
+    assert_eq!(
+        builder.get_config().deadline_watchdog,
+        Some(WatchdogConfig {
+            margin_ms: 100,
+            status: 502
+        })
+    );
+}
+
+fn load_error(table: Option<&str>, vars: &[(&str, &str)]) -> ConfigError {
+    with_env(vars, || match make_rocket(table).lambda().load_config() {
+        Ok(_) => panic!("expected the config to be invalid"),
+        Err(e) => e,
+    })
+}
+
+#[test]
+fn invalid_rocket_config_value() {
+    let error = load_error(Some("default_response_type = \"bytes\""), &[]);
+
+    assert_eq!(error.setting, "lambda.default_response_type");
+    assert_eq!(
+        error.to_string(),
+        "invalid setting lambda.default_response_type: expected \"auto\", \"text\" or \"binary\", found \"bytes\""
+    );
+}
+
+#[test]
+fn invalid_rocket_config_type() {
+    let error = load_error(Some("response_types = \"image/png\""), &[]);
+
+    assert_eq!(error.setting, "lambda.response_types");
+    assert_eq!(error.message, "expected a table, found string");
+
+    let error = load_error(Some("[response_types]\n\"image/png\" = true"), &[]);
+    assert_eq!(error.setting, "lambda.response_types.\"image/png\"");
+    assert_eq!(error.message, "expected a string, found boolean");
+}
+
+#[test]
+fn unknown_rocket_config_setting() {
+    let error = load_error(Some("default_response = \"binary\""), &[]);
+
+    assert_eq!(error.setting, "lambda.default_response");
+    assert_eq!(error.message, "unknown setting");
+}
+
+#[test]
+fn invalid_env_values() {
+    let error = load_error(None, &[("ROCKET_LAMB_BASE_PATH_BEHAVIOUR", "remount")]);
+    assert_eq!(error.setting, "ROCKET_LAMB_BASE_PATH_BEHAVIOUR");
+    assert_eq!(
+        error.message,
+        "expected \"remount_and_include\", \"include\" or \"exclude\", found \"remount\""
+    );
+
+    let error = load_error(None, &[("ROCKET_LAMB_RESPONSE_TYPES", "image/png")]);
+    assert_eq!(error.setting, "ROCKET_LAMB_RESPONSE_TYPES");
+    assert_eq!(
+        error.message,
+        "expected \"content-type=response-type\", found \"image/png\""
+    );
+}
+
+#[test]
+fn unknown_env_setting() {
+    let error = load_error(None, &[("ROCKET_LAMB_RESPONSE_TYPE", "binary")]);
+
+    assert_eq!(error.setting, "ROCKET_LAMB_RESPONSE_TYPE");
+    assert_eq!(error.message, "unknown setting");
+}
+
+#[test]
+fn invalid_other_settings() {
+    let error = load_error(Some("edge_continue_status = \"yes\""), &[]);
+    assert_eq!(error.setting, "lambda.edge_continue_status");
+    assert!(error.message.contains("invalid type"), "{}", error.message);
+
+    let error = load_error(
+        None,
+        &[("ROCKET_LAMB_DEADLINE_WATCHDOG", "{ margin_ms = 500 }")],
+    );
+    assert_eq!(error.setting, "ROCKET_LAMB_DEADLINE_WATCHDOG");
+    assert!(error.message.contains("status"), "{}", error.message);
+
+    let error = load_error(None, &[("ROCKET_LAMB_PATH_NORMALIZATION", "strip")]);
+    assert_eq!(error.setting, "ROCKET_LAMB_PATH_NORMALIZATION");
+    assert!(
+        error.message.contains("unknown variant"),
+        "{}",
+        error.message
+    );
+}
@@ -0,0 +1,187 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_http::{Body, Handler, Request};
+use lambda_runtime::Context;
+use rocket::State;
+use rocket_lamb::{Deadline, RocketExt};
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[post("/upper/<_path>")]
+fn remaining(_path: String, deadline: Deadline) -> String {
+    (deadline.remaining().as_secs() > 0).to_string()
+}
+
+#[post("/upper/<_path>", rank = 2)]
+fn no_deadline(_path: String) -> &'static str {
+    "no deadline"
+}
+
+#[post("/upper/<_path>")]
+fn slow(_path: String) -> &'static str {
+    thread::sleep(Duration::from_millis(500));
+    "done"
+}
+
+#[get("/slow/<_name>")]
+fn slow_named(_name: String) -> &'static str {
+    thread::sleep(Duration::from_millis(500));
+    "done"
+}
+
+#[post("/upper/<_path>")]
+fn panics_once(_path: String, panicked: State<AtomicBool>) -> &'static str {
+    if !panicked.swap(true, Ordering::SeqCst) {
+        panic!("first request");
+    }
+    "recovered"
+}
+
+fn get_request(json_file: &str) -> Result<Request, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(lambda_http::request::from_reader(file)?)
+}
+
+fn get_event(json_file: &str) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+fn context_with_deadline(from_now: Duration) -> Context {
+    let deadline = SystemTime::now().duration_since(UNIX_EPOCH).unwrap() + from_now;
+    let mut ctx = Context::default();
+    ctx.deadline = deadline.as_millis() as i64;
+    ctx
+}
+
+#[test]
+fn deadline_guard() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![remaining, no_deadline])
+        .lambda()
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context_with_deadline(Duration::from_secs(60)))?;
+    assert_eq!(*res.body(), Body::Text("true".to_string()));
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, Context::default())?;
+    assert_eq!(*res.body(), Body::Text("no deadline".to_string()));
+    Ok(())
+}
+
+#[test]
+fn watchdog_completes_in_time() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![slow])
+        .lambda()
+        .deadline_watchdog(Duration::from_millis(100), 504)
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context_with_deadline(Duration::from_secs(60)))?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("done".to_string()));
+    Ok(())
+}
+
+#[test]
+fn watchdog_times_out() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![slow])
+        .lambda()
+        .deadline_watchdog(Duration::from_millis(900), 503)
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context_with_deadline(Duration::from_secs(1)))?;
+    assert_eq!(res.status(), 503);
+    assert_eq!(*res.body(), Body::Empty);
+
+    // The next invocation waits for the overrunning request, then is processed as normal.
+    let req = get_request("upper")?;
+    let res = handler.run(req, context_with_deadline(Duration::from_secs(60)))?;
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("done".to_string()));
+    Ok(())
+}
+
+#[test]
+fn watchdog_does_not_wait_for_overrunning_request_past_deadline() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![slow])
+        .lambda()
+        .deadline_watchdog(Duration::from_millis(900), 503)
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context_with_deadline(Duration::from_secs(1)))?;
+    assert_eq!(res.status(), 503);
+
+    // The overrunning request is still running when this invocation's watchdog responds.
+    let req = get_request("upper")?;
+    let res = handler.run(req, context_with_deadline(Duration::from_secs(1)))?;
+    assert_eq!(res.status(), 503);
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context_with_deadline(Duration::from_secs(60)))?;
+    assert_eq!(res.status(), 200);
+    Ok(())
+}
+
+#[test]
+fn watchdog_recovers_from_panic() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![panics_once])
+        .manage(AtomicBool::new(false))
+        .lambda()
+        .deadline_watchdog(Duration::from_millis(100), 504)
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let err = handler
+        .run(req, context_with_deadline(Duration::from_secs(60)))
+        .unwrap_err();
+    assert!(err.to_string().contains("panicked"), "{}", err);
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context_with_deadline(Duration::from_secs(60)))?;
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("recovered".to_string()));
+    Ok(())
+}
+
+#[test]
+fn watchdog_reports_overrunning_route() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![slow_named])
+        .lambda()
+        .deadline_watchdog(Duration::from_millis(900), 503)
+        .into_handler();
+
+    // Lambda@Edge requests fail when the watchdog responds, with an error naming the route.
+    let mut event = get_event("edge_origin_request")?;
+    event["Records"][0]["cf"]["request"]["uri"] = Value::from("/slow/request");
+    let err = lambda_runtime::Handler::run(
+        &mut handler,
+        event,
+        context_with_deadline(Duration::from_secs(1)),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("GET /slow/<_name> did not complete before the invocation's deadline"),
+        "{}",
+        err
+    );
+    Ok(())
+}
@@ -0,0 +1,84 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_runtime::{Context, Handler};
+use rocket::http::Status;
+use rocket_lamb::RocketExt;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs::File;
+
+#[post("/invoke", format = "json", data = "<body>")]
+fn invoke(body: String) -> Result<String, Status> {
+    let input: Value = serde_json::from_str(&body).map_err(|_| Status::BadRequest)?;
+    match input["name"].as_str() {
+        Some(name) => Ok(json!({ "greeting": format!("Hello, {}!", name) }).to_string()),
+        None => Err(Status::UnprocessableEntity),
+    }
+}
+
+#[post("/empty")]
+fn empty() {}
+
+fn make_rocket() -> rocket::Rocket {
+    rocket::ignite().mount("/", routes![invoke, empty])
+}
+
+fn get_event(json_file: &str) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+#[test]
+fn direct_invocation() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .direct_invocation_route("/invoke")
+        .into_handler();
+
+    let event = get_event("direct")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res, json!({ "greeting": "Hello, rocket!" }));
+    Ok(())
+}
+
+#[test]
+fn direct_invocation_empty_response() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .direct_invocation_route("/empty")
+        .into_handler();
+
+    let event = get_event("direct")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res, Value::Null);
+    Ok(())
+}
+
+#[test]
+fn direct_invocation_error_status() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .direct_invocation_route("/invoke")
+        .into_handler();
+
+    let res = handler.run(json!({ "count": 2 }), Context::default());
+
+    assert!(res.is_err(), "Invocation should fail");
+    Ok(())
+}
+
+#[test]
+fn direct_invocation_not_configured() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let event = get_event("direct")?;
+    let res = handler.run(event, Context::default());
+
+    assert!(res.is_err(), "Invocation should fail");
+    Ok(())
+}
@@ -0,0 +1,109 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_runtime::{Context, Handler};
+use rocket::http::{ContentType, Header, Status};
+use rocket::response::Response;
+use rocket_lamb::RocketExt;
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+use std::io::Cursor;
+
+#[get("/edge/hello?<name>")]
+fn hello(name: String) -> String {
+    format!("Hello, {}!", name)
+}
+
+#[get("/edge/hello", rank = 2)]
+fn pass_through<'r>() -> Response<'r> {
+    Response::build()
+        .status(Status::new(299, "Continue To Origin"))
+        .header(Header::new("X-Checked-By", "rocket"))
+        .header(ContentType::Plain)
+        .sized_body(Cursor::new("Checked"))
+        .finalize()
+}
+
+#[get("/edge/large")]
+fn large() -> String {
+    "a".repeat(2 * 1024 * 1024)
+}
+
+fn make_rocket() -> rocket::Rocket {
+    rocket::ignite().mount("/", routes![hello, pass_through, large])
+}
+
+fn get_event(json_file: &str) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+#[test]
+fn edge_generated_response() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let event = get_event("edge_origin_request")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["status"], "200");
+    assert_eq!(res["statusDescription"], "OK");
+    assert_eq!(res["body"], "Hello, rocket!");
+    assert_eq!(res["bodyEncoding"], "text");
+    assert_eq!(
+        res["headers"]["content-type"][0]["value"],
+        "text/plain; charset=utf-8"
+    );
+    Ok(())
+}
+
+#[test]
+fn edge_continue_to_origin() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .edge_continue_status(299)
+        .into_handler();
+
+    let mut event = get_event("edge_origin_request")?;
+    event["Records"][0]["cf"]["request"]["querystring"] = Value::from("");
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["uri"], "/edge/hello");
+    assert_eq!(
+        res["headers"]["host"][0]["value"],
+        "d111111abcdef8.cloudfront.net"
+    );
+    assert_eq!(res["headers"]["x-checked-by"][0]["value"], "rocket");
+    assert!(res["headers"].get("server").is_none());
+    assert!(res["headers"].get("content-type").is_none());
+    assert!(res["headers"].get("content-length").is_none());
+    assert_eq!(res["origin"]["custom"]["domainName"], "example.com");
+    Ok(())
+}
+
+#[test]
+fn edge_response_too_large() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let mut event = get_event("edge_origin_request")?;
+    event["Records"][0]["cf"]["request"]["uri"] = Value::from("/edge/large");
+    let res = handler.run(event, Context::default());
+
+    assert!(res.is_err(), "Invocation should fail");
+    Ok(())
+}
+
+#[test]
+fn edge_extension_method_rejected() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let mut event = get_event("edge_origin_request")?;
+    event["Records"][0]["cf"]["request"]["method"] = Value::from("PURGE");
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["status"], "405");
+    assert_eq!(res["headers"]["allow"][0]["value"], "GET, HEAD");
+    Ok(())
+}
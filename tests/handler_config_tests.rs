@@ -0,0 +1,201 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_http::{Body, Handler, Request};
+use lambda_runtime::Context;
+use rocket::http::Method;
+use rocket_lamb::{
+    BasePathBehaviour, ExtensionMethodBehaviour, HandlerConfig, PathNormalization, ResponseType,
+    RocketExt, WatchdogConfig,
+};
+use serde_json::json;
+use std::error::Error;
+use std::fs::File;
+
+#[post("/upper/<path>?<query>", data = "<body>")]
+fn upper(path: String, query: String, body: String) -> String {
+    format!(
+        "{}, {}, {}",
+        path.to_uppercase(),
+        query.to_uppercase(),
+        body.to_uppercase()
+    )
+}
+
+fn make_rocket() -> rocket::Rocket {
+    rocket::ignite().mount("/", routes![upper])
+}
+
+fn get_request(json_file: &'static str) -> Result<Request, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(lambda_http::request::from_reader(file)?)
+}
+
+#[test]
+fn default_config_matches_builder_defaults() {
+    let builder = make_rocket().lambda();
+
+    assert_eq!(builder.get_config(), HandlerConfig::default());
+    assert_eq!(HandlerConfig::default().xray_tracing, false);
+    assert!(HandlerConfig::default()
+        .redacted_headers
+        .contains(&"authorization".to_owned()));
+}
+
+#[test]
+fn deserialize_with_defaults() -> Result<(), Box<dyn Error>> {
+    let config: HandlerConfig = serde_json::from_value(json!({
+        "response_types": { "image/png": "binary" },
+        "base_path_behaviour": "exclude",
+        "path_normalization": "collapse_slashes",
+        "default_extension_method": { "fallback": "/fallback" },
+        "extension_methods": { "PURGE": { "map_to": "delete" } },
+        "deadline_watchdog": { "margin_ms": 250, "status": 503 },
+    }))?;
+
+    assert_eq!(config.default_response_type, ResponseType::Auto);
+    assert_eq!(config.response_types["image/png"], ResponseType::Binary);
+    assert_eq!(config.base_path_behaviour, BasePathBehaviour::Exclude);
+    assert_eq!(
+        config.path_normalization,
+        PathNormalization::CollapseSlashes
+    );
+    assert_eq!(
+        config.default_extension_method,
+        ExtensionMethodBehaviour::Fallback("/fallback".to_owned())
+    );
+    assert_eq!(
+        config.extension_methods["PURGE"],
+        ExtensionMethodBehaviour::MapTo(Method::Delete)
+    );
+    assert_eq!(
+        config.deadline_watchdog,
+        Some(WatchdogConfig {
+            margin_ms: 250,
+            status: 503
+        })
+    );
+    assert_eq!(
+        config.redacted_headers,
+        HandlerConfig::default().redacted_headers
+    );
+    Ok(())
+}
+
+#[test]
+fn deserialize_rejects_invalid_config() {
+    let unknown_field = serde_json::from_value::<HandlerConfig>(json!({ "response_type": "text" }));
+    assert!(unknown_field.is_err());
+
+    let unknown_method = serde_json::from_value::<HandlerConfig>(json!({
+        "default_extension_method": { "map_to": "PURGE" },
+    }));
+    let error = unknown_method.unwrap_err().to_string();
+    assert!(error.contains("unknown HTTP method \"PURGE\""), "{}", error);
+}
+
+#[test]
+fn serialize_config() -> Result<(), Box<dyn Error>> {
+    let handler = make_rocket()
+        .lambda()
+        .response_type("IMAGE/PNG", ResponseType::Binary)
+        .extension_method("purge", ExtensionMethodBehaviour::MapTo(Method::Delete))
+        .deadline_watchdog(std::time::Duration::from_millis(500), 504)
+        .xray_daemon_address("127.0.0.1:3000")
+        .emf_metrics("MyApp")
+        .into_handler();
+
+    let value = serde_json::to_value(handler.config())?;
+
+    assert_eq!(value["default_response_type"], "auto");
+    assert_eq!(value["response_types"], json!({ "image/png": "binary" }));
+    assert_eq!(value["base_path_behaviour"], "remount_and_include");
+    assert_eq!(value["default_extension_method"], "reject");
+    assert_eq!(
+        value["extension_methods"],
+        json!({ "PURGE": { "map_to": "DELETE" } })
+    );
+    assert_eq!(
+        value["deadline_watchdog"],
+        json!({ "margin_ms": 500, "status": 504 })
+    );
+    assert_eq!(value["emf_metrics_namespace"], "MyApp");
+    assert_eq!(value["xray_tracing"], true);
+    assert_eq!(value["xray_daemon_address"], "127.0.0.1:3000");
+    Ok(())
+}
+
+#[test]
+fn serialize_xray_daemon_address_only_when_set() {
+    // Lambda sets AWS_XRAY_DAEMON_ADDRESS when active tracing is enabled.
+    std::env::set_var("AWS_XRAY_DAEMON_ADDRESS", "169.254.79.129:2000");
+    let builder = make_rocket().lambda().xray_tracing();
+
+    let config = builder.get_config();
+    assert!(config.xray_tracing);
+    assert_eq!(config.xray_daemon_address, None);
+
+    let builder = builder.xray_daemon_address("127.0.0.1:3000");
+    assert_eq!(
+        builder.get_config().xray_daemon_address.as_deref(),
+        Some("127.0.0.1:3000")
+    );
+}
+
+#[test]
+fn config_round_trip() -> Result<(), Box<dyn Error>> {
+    let config = HandlerConfig {
+        default_response_type: ResponseType::Text,
+        direct_invocation_route: Some("/invoke".to_owned()),
+        edge_continue_status: Some(404),
+        redacted_headers: vec!["x-api-key".to_owned()],
+        redacted_query_parameters: vec!["token".to_owned()],
+        emf_metrics_namespace: Some("MyApp".to_owned()),
+        ..HandlerConfig::default()
+    };
+    let json = serde_json::to_string(&config)?;
+
+    let handler = make_rocket()
+        .lambda()
+        .config(serde_json::from_str(&json)?)
+        .into_handler();
+
+    assert_eq!(handler.config(), config);
+    Ok(())
+}
+
+#[test]
+fn config_replaces_earlier_settings() {
+    let builder = make_rocket()
+        .lambda()
+        .response_type("image/png", ResponseType::Binary)
+        .redact_query_parameter("token")
+        .xray_tracing()
+        .config(HandlerConfig::default());
+
+    assert_eq!(builder.get_config(), HandlerConfig::default());
+    assert_eq!(builder.get_response_type("image/png"), ResponseType::Auto);
+}
+
+#[test]
+fn config_is_used_by_handler() -> Result<(), Box<dyn Error>> {
+    let config = HandlerConfig {
+        response_types: vec![("text/plain".to_owned(), ResponseType::Binary)]
+            .into_iter()
+            .collect(),
+        ..HandlerConfig::default()
+    };
+    let mut handler = make_rocket().lambda().config(config).into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        *res.body(),
+        Body::Binary("ONE, TWO, THREE".to_owned().into_bytes())
+    );
+    Ok(())
+}
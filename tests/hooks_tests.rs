@@ -0,0 +1,114 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_http::{Body, Handler, Request, Response};
+use lambda_runtime::Context;
+use rocket::State;
+use rocket_lamb::RocketExt;
+use std::error::Error;
+use std::fs::File;
+
+struct FunctionName(String);
+
+#[post("/upper/<_path>")]
+fn function_name(_path: String, name: State<FunctionName>) -> String {
+    name.0.clone()
+}
+
+#[post("/upper/<path>")]
+fn upper(path: String) -> String {
+    path.to_uppercase()
+}
+
+fn get_request(json_file: &str) -> Result<Request, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(lambda_http::request::from_reader(file)?)
+}
+
+fn context() -> Context {
+    let mut ctx = Context::default();
+    ctx.function_name = "my-function".to_owned();
+    ctx.aws_request_id = "request-1".to_owned();
+    ctx
+}
+
+#[test]
+fn on_cold_start() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![function_name])
+        .lambda()
+        .on_cold_start(|rocket, ctx| rocket.manage(FunctionName(ctx.function_name.clone())))
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("my-function".to_string()));
+    Ok(())
+}
+
+#[test]
+fn before_request_modifies_request() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .before_request(|req, _ctx| {
+            *req.uri_mut() = "/upper/modified".parse().unwrap();
+            None
+        })
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("MODIFIED".to_string()));
+    Ok(())
+}
+
+#[test]
+fn before_request_short_circuits() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .before_request(|_req, ctx| {
+            Some(
+                Response::builder()
+                    .status(503)
+                    .body(Body::Text(ctx.aws_request_id.clone()))
+                    .unwrap(),
+            )
+        })
+        .after_response(|_rocket_res, _res, _ctx| panic!("Rocket should not have responded"))
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context())?;
+
+    assert_eq!(res.status(), 503);
+    assert_eq!(*res.body(), Body::Text("request-1".to_string()));
+    Ok(())
+}
+
+#[test]
+fn after_response() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .after_response(|rocket_res, res, ctx| {
+            let value = format!("{} {}", rocket_res.status().code, ctx.aws_request_id);
+            res.headers_mut()
+                .insert("x-after-response", value.parse().unwrap());
+        })
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, context())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["x-after-response"], "200 request-1");
+    Ok(())
+}
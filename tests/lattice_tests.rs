@@ -0,0 +1,118 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_runtime::{Context, Handler};
+use rocket::http::uri::Origin;
+use rocket::http::Method;
+use rocket_lamb::{ExtensionMethodBehaviour, RocketExt};
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+
+#[post("/upper/<path>?<query>", data = "<body>")]
+fn upper(path: String, query: String, body: String) -> String {
+    format!(
+        "{}, {}, {}",
+        path.to_uppercase(),
+        query.to_uppercase(),
+        body.to_uppercase()
+    )
+}
+
+#[get("/query")]
+fn query(origin: &Origin) -> String {
+    origin.query().unwrap_or_default().to_owned()
+}
+
+#[get("/binary")]
+fn binary() -> &'static [u8] {
+    &[200, 201, 202]
+}
+
+fn make_rocket() -> rocket::Rocket {
+    rocket::ignite().mount("/", routes![upper, query, binary])
+}
+
+fn get_event(json_file: &str) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+#[test]
+fn lattice_text() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let event = get_event("lattice")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["statusDescription"], "200 OK");
+    assert_eq!(res["headers"]["content-type"], "text/plain; charset=utf-8");
+    assert_eq!(res["body"], "ONE, TWO, THREE");
+    assert_eq!(res["isBase64Encoded"], false);
+    Ok(())
+}
+
+#[test]
+fn lattice_binary() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let event = get_event("lattice_binary")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["headers"]["content-type"], "application/octet-stream");
+    assert_eq!(res["body"], "yMnK");
+    assert_eq!(res["isBase64Encoded"], true);
+    Ok(())
+}
+
+#[test]
+fn lattice_not_found() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let mut event = get_event("lattice_binary")?;
+    event["raw_path"] = Value::from("/missing");
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 404);
+    assert_eq!(res["statusDescription"], "404 Not Found");
+    Ok(())
+}
+
+#[test]
+fn lattice_extension_method() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .extension_method("propfind", ExtensionMethodBehaviour::MapTo(Method::Post))
+        .into_handler();
+
+    let mut event = get_event("lattice")?;
+    event["method"] = "PROPFIND".into();
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["body"], "ONE, TWO, THREE");
+    Ok(())
+}
+
+#[test]
+fn lattice_query_is_ordered() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let mut event = get_event("lattice_binary")?;
+    event["raw_path"] = Value::from("/query");
+    event["query_string_parameters"] = serde_json::json!({
+        "space": "x y",
+        "flag": "",
+        "b": ["2", "3"],
+        "a": "1",
+    });
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["body"], "a=1&b=2&b=3&flag&space=x%20y");
+    Ok(())
+}
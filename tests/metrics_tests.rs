@@ -0,0 +1,109 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_http::{Handler, Request};
+use lambda_runtime::Context;
+use rocket_lamb::{HandlerConfig, RocketExt};
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+#[post("/upper/<path>")]
+fn upper(path: String) -> String {
+    path.to_uppercase()
+}
+
+#[derive(Clone, Default)]
+struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedWriter {
+    fn documents(&self) -> Vec<Value> {
+        let output = self.0.lock().unwrap();
+        String::from_utf8_lossy(&output)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+fn get_request(json_file: &str) -> Result<Request, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(lambda_http::request::from_reader(file)?)
+}
+
+#[test]
+fn emits_metrics_per_route() -> Result<(), Box<dyn Error>> {
+    let writer = SharedWriter::default();
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .emf_metrics_writer("MyService", writer.clone())
+        .into_handler();
+
+    handler.run(get_request("upper")?, Context::default())?;
+    handler.run(get_request("not_found")?, Context::default())?;
+
+    let documents = writer.documents();
+    assert_eq!(documents.len(), 2);
+
+    let metrics = &documents[0];
+    let definition = &metrics["_aws"]["CloudWatchMetrics"][0];
+    assert_eq!(definition["Namespace"], "MyService");
+    assert_eq!(
+        definition["Dimensions"][0],
+        serde_json::json!(["Stage", "Route"])
+    );
+    assert_eq!(metrics["Stage"], "Prod");
+    assert_eq!(metrics["Route"], "POST /upper/<path>");
+    assert_eq!(metrics["RouteName"], "upper");
+    assert_eq!(metrics["2xx"], 1);
+    assert_eq!(metrics["4xx"], 0);
+    assert_eq!(metrics["ResponseBytes"], 3);
+    assert_eq!(metrics["Base64Responses"], 0);
+    assert_eq!(metrics["ColdStart"], 1);
+    assert!(metrics["Latency"].is_number());
+
+    let not_found = &documents[1];
+    assert_eq!(not_found["Route"], "unmatched");
+    assert_eq!(not_found["4xx"], 1);
+    assert_eq!(not_found["ColdStart"], 0);
+    Ok(())
+}
+
+#[test]
+fn config_keeps_metrics_writer() -> Result<(), Box<dyn Error>> {
+    let writer = SharedWriter::default();
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .emf_metrics_writer("MyService", writer.clone())
+        .config(HandlerConfig {
+            emf_metrics_namespace: Some("OtherService".to_owned()),
+            ..HandlerConfig::default()
+        })
+        .into_handler();
+
+    handler.run(get_request("upper")?, Context::default())?;
+
+    let documents = writer.documents();
+    assert_eq!(documents.len(), 1);
+    assert_eq!(
+        documents[0]["_aws"]["CloudWatchMetrics"][0]["Namespace"],
+        "OtherService"
+    );
+    Ok(())
+}
@@ -0,0 +1,184 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use rocket_lamb::{ResponseType, RocketExt};
+use serde_json::json;
+use std::path::PathBuf;
+
+#[get("/")]
+fn index() -> &'static str {
+    "index"
+}
+
+#[get("/items/<id>")]
+fn get_item(id: u32) -> String {
+    id.to_string()
+}
+
+#[delete("/items/<id>")]
+fn delete_item(id: u32) -> String {
+    id.to_string()
+}
+
+#[get("/items/<id>", rank = 2)]
+fn get_item_by_name(id: String) -> String {
+    id
+}
+
+#[get("/files/<path..>")]
+fn file(path: PathBuf) -> String {
+    path.display().to_string()
+}
+
+#[get("/items/<name>/edit")]
+fn edit_item(name: String) -> String {
+    name
+}
+
+#[get("/<path..>", rank = 10)]
+fn catch_all(path: PathBuf) -> String {
+    path.display().to_string()
+}
+
+#[get("/search?<q>")]
+fn search(q: String) -> String {
+    q
+}
+
+const INTEGRATION_URI: &str = "arn:aws:apigateway:eu-west-1:lambda:path/2015-03-31/functions/arn:aws:lambda:eu-west-1:123456789012:function:test/invocations";
+
+fn make_document(builder: rocket_lamb::RocketHandlerBuilder) -> serde_json::Value {
+    builder.openapi("Test API", "1.2.3", INTEGRATION_URI)
+}
+
+fn make_rocket() -> rocket::Rocket {
+    rocket::ignite()
+        .mount(
+            "/",
+            routes![index, get_item, delete_item, get_item_by_name, search],
+        )
+        .mount("/static", routes![file])
+}
+
+#[test]
+fn document_info() {
+    let document = make_document(make_rocket().lambda());
+
+    assert_eq!(document["openapi"], "3.0.1");
+    assert_eq!(
+        document["info"],
+        json!({ "title": "Test API", "version": "1.2.3" })
+    );
+    assert!(document
+        .get("x-amazon-apigateway-binary-media-types")
+        .is_none());
+}
+
+#[test]
+fn paths_are_translated() {
+    let document = make_document(make_rocket().lambda());
+    let paths = document["paths"].as_object().unwrap();
+
+    let mut keys: Vec<&str> = paths.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![
+            "/",
+            "/items/{id}",
+            "/search",
+            "/static/files",
+            "/static/files/{path+}"
+        ]
+    );
+
+    let methods: Vec<&str> = paths["/items/{id}"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    assert_eq!(methods, vec!["delete", "get"]);
+
+    assert_eq!(
+        paths["/static/files/{path+}"]["get"]["parameters"],
+        json!([{ "name": "path", "in": "path", "required": true, "schema": { "type": "string" } }])
+    );
+    assert!(paths["/search"]["get"].get("parameters").is_none());
+    assert!(paths["/static/files"]["get"].get("parameters").is_none());
+}
+
+#[test]
+fn operations_use_proxy_integration() {
+    let document = make_document(make_rocket().lambda());
+    let operation = &document["paths"]["/items/{id}"]["get"];
+
+    // The highest priority route is used when several routes share a method and path.
+    assert_eq!(operation["operationId"], "get_item");
+    assert_eq!(
+        operation["x-amazon-apigateway-integration"],
+        json!({
+            "type": "aws_proxy",
+            "httpMethod": "POST",
+            "uri": INTEGRATION_URI,
+            "passthroughBehavior": "when_no_match",
+        })
+    );
+}
+
+#[test]
+fn binary_media_types_from_response_types() {
+    let document = make_document(
+        make_rocket()
+            .lambda()
+            .response_type("image/png", ResponseType::Binary)
+            .response_type("application/octet-stream", ResponseType::Binary)
+            .response_type("text/plain", ResponseType::Text),
+    );
+    assert_eq!(
+        document["x-amazon-apigateway-binary-media-types"],
+        json!(["application/octet-stream", "image/png"])
+    );
+
+    let document = make_document(
+        make_rocket()
+            .lambda()
+            .default_response_type(ResponseType::Binary),
+    );
+    assert_eq!(
+        document["x-amazon-apigateway-binary-media-types"],
+        json!(["*/*"])
+    );
+}
+
+#[test]
+fn parameter_names_are_unified() {
+    let document = make_document(
+        rocket::ignite()
+            .mount("/", routes![get_item, edit_item])
+            .lambda(),
+    );
+    let paths = document["paths"].as_object().unwrap();
+
+    let mut keys: Vec<&str> = paths.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(keys, vec!["/items/{id}", "/items/{id}/edit"]);
+    assert_eq!(
+        paths["/items/{id}/edit"]["get"]["parameters"][0]["name"],
+        "id"
+    );
+}
+
+#[test]
+fn greedy_route_includes_parent() {
+    let document = make_document(rocket::ignite().mount("/", routes![catch_all]).lambda());
+    let paths = document["paths"].as_object().unwrap();
+
+    let mut keys: Vec<&str> = paths.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(keys, vec!["/", "/{path+}"]);
+    assert_eq!(paths["/"]["get"]["operationId"], "catch_all");
+    assert!(paths["/"]["get"].get("parameters").is_none());
+}
@@ -0,0 +1,219 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_http::{Body, Handler, Request};
+use lambda_runtime::Context;
+use rocket_lamb::{AttributeValue, InMemorySpanExporter, OtlpHttpExporter, RocketExt, SpanContext};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[post("/upper/<_path>")]
+fn span(_path: String, span: SpanContext) -> String {
+    span.traceparent()
+}
+
+fn get_request(json_file: &str) -> Result<Request, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(lambda_http::request::from_reader(file)?)
+}
+
+/// Starts a collector that accepts one request, responds with `status_line` and sends the request
+/// body to the returned receiver.
+fn mock_collector(status_line: &'static str) -> Result<(String, Receiver<String>), Box<dyn Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let endpoint = format!("http://{}/v1/traces", listener.local_addr()?);
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        write!(
+            reader.get_mut(),
+            "{}\r\nContent-Length: 0\r\n\r\n",
+            status_line
+        )
+        .unwrap();
+        sender.send(String::from_utf8(body).unwrap()).unwrap();
+    });
+    Ok((endpoint, receiver))
+}
+
+#[test]
+fn creates_server_span() -> Result<(), Box<dyn Error>> {
+    let exporter = Arc::new(InMemorySpanExporter::default());
+    let mut handler = rocket::ignite()
+        .mount("/", routes![span])
+        .lambda()
+        .opentelemetry(exporter.clone())
+        .into_handler();
+
+    let mut ctx = Context::default();
+    ctx.aws_request_id = "request-1".to_owned();
+    let res = handler.run(get_request("upper")?, ctx)?;
+
+    let spans = exporter.spans();
+    assert_eq!(spans.len(), 1);
+    let span = &spans[0];
+    assert_eq!(span.name, "POST /upper/<_path>");
+    assert_eq!(span.parent_span_id, None);
+    assert_eq!(*res.body(), Body::Text(span.context.traceparent()));
+    assert_eq!(
+        span.attribute("http.route"),
+        Some(&AttributeValue::String("/upper/<_path>".to_owned()))
+    );
+    assert_eq!(
+        span.attribute("http.response.status_code"),
+        Some(&AttributeValue::Int(200))
+    );
+    assert_eq!(
+        span.attribute("faas.invocation_id"),
+        Some(&AttributeValue::String("request-1".to_owned()))
+    );
+    assert_eq!(
+        span.attribute("faas.coldstart"),
+        Some(&AttributeValue::Bool(true))
+    );
+    assert!(!span.is_error);
+    Ok(())
+}
+
+#[test]
+fn continues_traceparent() -> Result<(), Box<dyn Error>> {
+    let exporter = Arc::new(InMemorySpanExporter::default());
+    let mut handler = rocket::ignite()
+        .mount("/", routes![span])
+        .lambda()
+        .opentelemetry(exporter.clone())
+        .into_handler();
+
+    let mut req = get_request("upper")?;
+    req.headers_mut().insert(
+        "traceparent",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".parse()?,
+    );
+    handler.run(req, Context::default())?;
+
+    let span = &exporter.spans()[0];
+    assert_eq!(span.context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_ne!(span.context.span_id, "00f067aa0ba902b7");
+    assert_eq!(span.parent_span_id, Some("00f067aa0ba902b7".to_owned()));
+    Ok(())
+}
+
+#[test]
+fn unsampled_traces_are_not_exported() -> Result<(), Box<dyn Error>> {
+    let exporter = Arc::new(InMemorySpanExporter::default());
+    let mut handler = rocket::ignite()
+        .mount("/", routes![span])
+        .lambda()
+        .opentelemetry(exporter.clone())
+        .into_handler();
+
+    let mut req = get_request("upper")?;
+    req.headers_mut().insert(
+        "traceparent",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00".parse()?,
+    );
+    let res = handler.run(req, Context::default())?;
+
+    // The context is still available to routes, so that the decision is propagated downstream.
+    match res.body() {
+        Body::Text(traceparent) => assert!(traceparent.ends_with("-00"), "{}", traceparent),
+        body => panic!("Expected a text body, got {:?}", body),
+    }
+    assert!(exporter.spans().is_empty());
+    Ok(())
+}
+
+#[test]
+fn continues_xray_trace() -> Result<(), Box<dyn Error>> {
+    let exporter = Arc::new(InMemorySpanExporter::default());
+    let mut handler = rocket::ignite()
+        .mount("/", routes![span])
+        .lambda()
+        .opentelemetry(exporter.clone())
+        .into_handler();
+
+    let mut ctx = Context::default();
+    ctx.xray_trace_id = Some(
+        "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1".to_owned(),
+    );
+    handler.run(get_request("upper")?, ctx)?;
+
+    let span = &exporter.spans()[0];
+    assert_eq!(span.context.trace_id, "5759e988bd862e3fe1be46a994272793");
+    assert_eq!(span.parent_span_id, Some("53995c3f42cd8ad8".to_owned()));
+    Ok(())
+}
+
+#[test]
+fn exports_to_otlp_collector() -> Result<(), Box<dyn Error>> {
+    let (endpoint, requests) = mock_collector("HTTP/1.1 200 OK")?;
+    let mut handler = rocket::ignite()
+        .mount("/", routes![span])
+        .lambda()
+        .opentelemetry(OtlpHttpExporter::new(&endpoint))
+        .into_handler();
+
+    let res = handler.run(get_request("upper")?, Context::default())?;
+    assert_eq!(res.status(), 200);
+
+    let body: serde_json::Value = serde_json::from_str(&requests.recv()?)?;
+    let span = &body["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+    assert_eq!(span["name"], "POST /upper/<_path>");
+    Ok(())
+}
+
+#[test]
+fn collector_errors_do_not_fail_requests() -> Result<(), Box<dyn Error>> {
+    let (endpoint, requests) = mock_collector("HTTP/1.1 500 Internal Server Error")?;
+    let mut handler = rocket::ignite()
+        .mount("/", routes![span])
+        .lambda()
+        .opentelemetry(OtlpHttpExporter::new(&endpoint))
+        .into_handler();
+
+    let res = handler.run(get_request("upper")?, Context::default())?;
+    assert_eq!(res.status(), 200);
+    requests.recv()?;
+    Ok(())
+}
+
+#[test]
+fn slow_collectors_do_not_delay_requests() -> Result<(), Box<dyn Error>> {
+    // The collector accepts connections, but never responds.
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let endpoint = format!("http://{}/v1/traces", listener.local_addr()?);
+    let mut handler = rocket::ignite()
+        .mount("/", routes![span])
+        .lambda()
+        .opentelemetry(OtlpHttpExporter::new(&endpoint).timeout(Duration::from_millis(100)))
+        .into_handler();
+
+    let start = Instant::now();
+    let res = handler.run(get_request("upper")?, Context::default())?;
+    assert_eq!(res.status(), 200);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    drop(listener);
+    Ok(())
+}
@@ -0,0 +1,82 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_http::{Body, Handler, Request};
+use lambda_runtime::Context;
+use rocket::State;
+use rocket_lamb::{PathParameters, RocketExt, StageVariables};
+use std::error::Error;
+use std::fs::File;
+
+struct TableName(String);
+
+#[post("/upper/<path>")]
+fn params(path: String, params: PathParameters, variables: StageVariables) -> String {
+    format!(
+        "{}, {}, {}",
+        path,
+        params.get("proxy").unwrap_or_default(),
+        variables.get("table").unwrap_or_default()
+    )
+}
+
+#[post("/upper/<_path>", rank = 2)]
+fn table(_path: String, table: State<TableName>) -> String {
+    table.0.clone()
+}
+
+fn get_request(json_file: &str) -> Result<Request, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(lambda_http::request::from_reader(file)?)
+}
+
+#[test]
+fn path_parameters_and_stage_variables() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![params])
+        .lambda()
+        .into_handler();
+
+    let req = get_request("stage_variables")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("one, upper/one, prod".to_string()));
+    Ok(())
+}
+
+#[test]
+fn empty_stage_variables() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![params])
+        .lambda()
+        .into_handler();
+
+    let req = get_request("upper")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("one, upper/one, ".to_string()));
+    Ok(())
+}
+
+#[test]
+fn configure_from_stage_variables() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![table])
+        .lambda()
+        .configure_from_stage_variables(|rocket, variables| {
+            let table = variables.get("table").unwrap_or("dev").to_owned();
+            rocket.manage(TableName(table))
+        })
+        .into_handler();
+
+    let req = get_request("stage_variables")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("prod".to_string()));
+    Ok(())
+}
@@ -0,0 +1,139 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_runtime::{Context, Handler};
+use rocket::http::uri::Origin;
+use rocket_lamb::{BasePathBehaviour, RocketExt};
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+
+#[catch(404)]
+fn not_found(req: &rocket::Request) -> String {
+    req.uri().to_string()
+}
+
+#[get("/items/<id>")]
+fn item<'r>(id: String, origin: &'r Origin<'r>) -> String {
+    format!("{} {}", id, origin.path())
+}
+
+#[get("/<name>", rank = 2)]
+fn name<'r>(name: String, origin: &'r Origin<'r>) -> String {
+    format!("{} {}", name, origin.path())
+}
+
+fn make_rocket() -> rocket::Rocket {
+    rocket::ignite()
+        .mount("/", routes![item, name])
+        .register(catchers![not_found])
+}
+
+fn get_event(json_file: &str) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+macro_rules! test_case {
+    ($name:ident, $file:expr, $status:expr, $body:expr) => {
+        test_case!($name, RemountAndInclude, $file, $status, $body);
+    };
+    ($name:ident, $path_behaviour:ident, $file:expr, $status:expr, $body:expr) => {
+        #[test]
+        fn $name() -> Result<(), Box<dyn Error>> {
+            let mut handler = make_rocket()
+                .lambda()
+                .base_path_behaviour(BasePathBehaviour::$path_behaviour)
+                .into_handler();
+
+            let event = get_event($file)?;
+            let res = handler.run(event, Context::default())?;
+
+            assert_eq!(res["statusCode"], $status);
+            assert_eq!(res["body"], $body);
+            Ok(())
+        }
+    };
+}
+
+test_case!(
+    encoded_space,
+    "path_encoded_space",
+    200,
+    "hello world /base-path/hello%20world"
+);
+test_case!(
+    encoded_space_exclude_base,
+    Exclude,
+    "path_encoded_space",
+    200,
+    "hello world /hello%20world"
+);
+test_case!(
+    decoded_space,
+    "path_decoded_space",
+    200,
+    "hello world /base-path/hello%20world"
+);
+test_case!(
+    encoded_slash,
+    "path_encoded_slash",
+    200,
+    "a/b /base-path/items/a%2Fb"
+);
+test_case!(
+    encoded_slash_exclude_base,
+    Exclude,
+    "path_encoded_slash",
+    200,
+    "a/b /items/a%2Fb"
+);
+test_case!(
+    encoded_unicode,
+    "path_encoded_unicode",
+    200,
+    "café /base-path/caf%C3%A9"
+);
+test_case!(
+    decoded_unicode,
+    "path_decoded_unicode",
+    200,
+    "café /base-path/caf%C3%A9"
+);
+test_case!(
+    alb_encoded_slash,
+    "path_alb_encoded_slash",
+    200,
+    "a/b /items/a%2Fb"
+);
+test_case!(
+    http_api_encoded_space,
+    "path_http_api_encoded_space",
+    200,
+    "hello world /Prod/items/hello%20world"
+);
+test_case!(
+    http_api_encoded_space_exclude_base,
+    Exclude,
+    "path_http_api_encoded_space",
+    200,
+    "hello world /items/hello%20world"
+);
+
+#[test]
+fn decoded_pipe_lambda_http() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let file = File::open("tests/requests/path_decoded_pipe.json")?;
+    let req = lambda_http::request::from_reader(file)?;
+    let res = lambda_http::Handler::run(&mut handler, req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        *res.body(),
+        lambda_http::Body::Text("a|b /base-path/a%7Cb".to_string())
+    );
+    Ok(())
+}
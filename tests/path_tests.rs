@@ -6,7 +6,7 @@ extern crate rocket;
 use lambda_http::{Body, Handler, Request};
 use lambda_runtime::Context;
 use rocket::http::uri::Origin;
-use rocket_lamb::{BasePathBehaviour, RocketExt};
+use rocket_lamb::{BasePathBehaviour, PathNormalization, RocketExt};
 use std::error::Error;
 use std::fs::File;
 
@@ -36,11 +36,15 @@ macro_rules! test_case {
         test_case!($name, RemountAndInclude, $file, $status, $path);
     };
     ($name:ident, $path_behaviour:ident, $file:expr, $status:expr, $path:expr) => {
+        test_case!($name, $path_behaviour, Preserve, $file, $status, $path);
+    };
+    ($name:ident, $path_behaviour:ident, $normalization:ident, $file:expr, $status:expr, $path:expr) => {
         #[test]
         fn $name() -> Result<(), Box<dyn Error>> {
             let mut handler = make_rocket()
                 .lambda()
                 .base_path_behaviour(BasePathBehaviour::$path_behaviour)
+                .path_normalization(PathNormalization::$normalization)
                 .into_handler();
 
             let req = get_request($file)?;
@@ -121,3 +125,59 @@ test_case!(
     200,
     "/path/"
 );
+
+test_case!(
+    api_gateway_strip_trailing_slash,
+    RemountAndInclude,
+    StripTrailingSlash,
+    "path_api_gateway",
+    200,
+    "/Prod/path"
+);
+test_case!(
+    custom_domain_with_base_path_exclude_strip_trailing_slash,
+    Exclude,
+    StripTrailingSlash,
+    "path_custom_domain_with_base",
+    200,
+    "/path"
+);
+test_case!(
+    application_load_balancer_collapse_slashes,
+    RemountAndInclude,
+    CollapseSlashes,
+    "path_alb_duplicate_slashes",
+    200,
+    "/path/"
+);
+
+#[test]
+fn api_gateway_redirect_to_canonical() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .path_normalization(PathNormalization::Redirect)
+        .into_handler();
+
+    let req = get_request("path_api_gateway")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 308);
+    assert_eq!(res.headers()["location"], "/Prod/path");
+    assert!(res.body().is_empty(), "Response body should be empty");
+    Ok(())
+}
+
+#[test]
+fn application_load_balancer_redirect_to_canonical() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .path_normalization(PathNormalization::Redirect)
+        .into_handler();
+
+    let req = get_request("path_alb_duplicate_slashes")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 308);
+    assert_eq!(res.headers()["location"], "/path");
+    Ok(())
+}
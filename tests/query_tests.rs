@@ -0,0 +1,178 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_runtime::{Context, Handler};
+use proptest::prelude::*;
+use rocket::http::uri::Origin;
+use rocket::http::Cookies;
+use rocket_lamb::{QueryReconstruction, RocketExt};
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::fs::File;
+
+#[get("/query")]
+fn query<'r>(origin: &'r Origin<'r>) -> &'r str {
+    origin.query().unwrap_or_default()
+}
+
+#[get("/cookies")]
+fn cookies(cookies: Cookies) -> String {
+    let value = |name| cookies.get(name).map(|c| c.value().to_owned());
+    format!("{:?} {:?}", value("session"), value("theme"))
+}
+
+fn make_rocket() -> rocket::Rocket {
+    rocket::ignite().mount("/", routes![query, cookies])
+}
+
+fn get_event(json_file: &str) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+macro_rules! test_case {
+    ($name:ident, $behaviour:ident, $file:expr, $query:expr) => {
+        #[test]
+        fn $name() -> Result<(), Box<dyn Error>> {
+            let mut handler = make_rocket()
+                .lambda()
+                .query_reconstruction(QueryReconstruction::$behaviour)
+                .into_handler();
+
+            let event = get_event($file)?;
+            let res = handler.run(event, Context::default())?;
+
+            assert_eq!(res["statusCode"], 200);
+            assert_eq!(res["body"], $query);
+            Ok(())
+        }
+    };
+}
+
+test_case!(
+    rest_api_ordered,
+    Ordered,
+    "query_rest",
+    "a=1&b=2&b=3&flag&space=x%20y"
+);
+test_case!(
+    http_api_raw,
+    Ordered,
+    "query_http_api",
+    "b=2&a=1&b=3&flag&plus=x+y%20z"
+);
+test_case!(
+    http_api_raw_unordered,
+    Unordered,
+    "query_http_api",
+    "b=2&a=1&b=3&flag&plus=x+y%20z"
+);
+test_case!(
+    alb_not_encoded_again,
+    Ordered,
+    "alb_multi_value",
+    "name=hello%20world"
+);
+
+#[test]
+fn http_api_cookies() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let mut event = get_event("query_http_api")?;
+    event["rawPath"] = json!("/Prod/cookies");
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["body"], r#"Some("abc") Some("dark")"#);
+    assert_eq!(res["headers"]["content-type"], "text/plain; charset=utf-8");
+    assert_eq!(res.get("multiValueHeaders"), None);
+    Ok(())
+}
+
+#[test]
+fn rest_api_ordered_lambda_http() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let file = File::open("tests/requests/query_rest.json")?;
+    let req = lambda_http::request::from_reader(file)?;
+    let res = lambda_http::Handler::run(&mut handler, req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        *res.body(),
+        lambda_http::Body::Text("a=1&b=2&b=3&flag&space=x%20y".to_string())
+    );
+    Ok(())
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+            decoded.push(u8::from_str_radix(hex, 16).unwrap());
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).unwrap()
+}
+
+/// Groups the pairs by key, as API Gateway does when building `multiValueQueryStringParameters`.
+fn group_pairs(pairs: &[(String, String)]) -> Map<String, Value> {
+    let mut grouped = Map::new();
+    for (key, value) in pairs {
+        let values = grouped.entry(key.clone()).or_insert_with(|| json!([]));
+        values.as_array_mut().unwrap().push(json!(value));
+    }
+    grouped
+}
+
+proptest! {
+    #[test]
+    fn rest_api_query_round_trip(
+        pairs in prop::collection::vec(("[a-z&=+% é]{1,4}", "[a-zA-Z0-9&=+% é/?#]{0,4}"), 0..6)
+    ) {
+        let mut handler = make_rocket().lambda().into_handler();
+
+        let grouped = group_pairs(&pairs);
+        let mut event = get_event("query_rest").unwrap();
+        event["queryStringParameters"] = Value::Object(
+            grouped
+                .iter()
+                .map(|(k, v)| (k.clone(), v.as_array().unwrap().last().unwrap().clone()))
+                .collect(),
+        );
+        event["multiValueQueryStringParameters"] = Value::Object(grouped.clone());
+        let res = handler.run(event, Context::default()).unwrap();
+
+        let query = res["body"].as_str().unwrap_or_default();
+        let round_tripped: Vec<(String, String)> = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let mut parts = pair.splitn(2, '=');
+                let key = percent_decode(parts.next().unwrap());
+                let value = percent_decode(parts.next().unwrap_or_default());
+                (key, value)
+            })
+            .collect();
+        let expected: Vec<(String, String)> = grouped
+            .iter()
+            .flat_map(|(k, values)| {
+                values
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(move |v| (k.clone(), v.as_str().unwrap().to_owned()))
+            })
+            .collect();
+        prop_assert_eq!(round_tripped, expected);
+    }
+}
@@ -0,0 +1,247 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_runtime::{Context, Handler};
+use rocket_lamb::{
+    load_recordings, DirectoryRecordingSink, InMemoryRecordingSink, RocketExt, RocketHandler,
+};
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+use std::sync::Arc;
+
+#[post("/upper/<path>", data = "<body>")]
+fn upper(path: String, body: String) -> String {
+    format!("{} {}", path, body).to_uppercase()
+}
+
+#[post("/upper/<path>", data = "<body>")]
+fn lower(path: String, body: String) -> String {
+    format!("{} {}", path, body).to_lowercase()
+}
+
+fn get_event(json_file: &str) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+fn get_authorized_event() -> Result<Value, Box<dyn Error>> {
+    let mut event = get_event("upper")?;
+    event["headers"]["Authorization"] = "Bearer secret".into();
+    event["multiValueHeaders"]["Authorization"] = serde_json::json!(["Bearer secret"]);
+    event["requestContext"]["authorizer"] = serde_json::json!({ "claims": { "sub": "user-1" } });
+    Ok(event)
+}
+
+fn make_handler(sink: Arc<InMemoryRecordingSink>) -> RocketHandler {
+    rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .record_events(sink)
+        .redact_query_parameter("query")
+        .into_handler()
+}
+
+#[test]
+fn records_redacted_events_and_responses() -> Result<(), Box<dyn Error>> {
+    let sink = Arc::new(InMemoryRecordingSink::default());
+    let mut handler = make_handler(sink.clone());
+
+    let mut ctx = Context::default();
+    ctx.aws_request_id = "request-1".to_owned();
+    let res = handler.run(get_authorized_event()?, ctx)?;
+
+    let invocations = sink.invocations();
+    assert_eq!(invocations.len(), 1);
+    let invocation = &invocations[0];
+    assert_eq!(invocation.request_id, "request-1");
+    assert_eq!(invocation.event["headers"]["Authorization"], "REDACTED");
+    assert_eq!(
+        invocation.event["multiValueHeaders"]["Authorization"][0],
+        "REDACTED"
+    );
+    assert_eq!(invocation.event["headers"]["Host"], "example.com");
+    assert_eq!(
+        invocation.event["queryStringParameters"]["query"],
+        "REDACTED"
+    );
+    assert_eq!(
+        invocation.event["requestContext"]["authorizer"]["claims"]["sub"],
+        "REDACTED"
+    );
+    assert_eq!(
+        invocation.event["requestContext"]["identity"]["sourceIp"],
+        "REDACTED"
+    );
+    assert_eq!(invocation.event["body"], "REDACTED");
+    let response = invocation.result.as_ref().unwrap();
+    assert_eq!(response["body"], "REDACTED");
+    assert_eq!(response["statusCode"], res["statusCode"]);
+    Ok(())
+}
+
+#[test]
+fn records_redacted_edge_events() -> Result<(), Box<dyn Error>> {
+    let sink = Arc::new(InMemoryRecordingSink::default());
+    let mut handler = make_handler(sink.clone());
+
+    let mut event = get_event("edge_origin_request")?;
+    let request = &mut event["Records"][0]["cf"]["request"];
+    request["headers"]["authorization"] =
+        serde_json::json!([{ "key": "Authorization", "value": "Bearer secret" }]);
+    request["headers"]["cookie"] =
+        serde_json::json!([{ "key": "Cookie", "value": "session=secret" }]);
+    request["querystring"] = "name=rocket&query=secret".into();
+    handler.run(event, Context::default())?;
+
+    let invocation = &sink.invocations()[0];
+    let request = &invocation.event["Records"][0]["cf"]["request"];
+    assert_eq!(
+        request["headers"]["authorization"],
+        serde_json::json!([{ "key": "Authorization", "value": "REDACTED" }])
+    );
+    assert_eq!(request["headers"]["cookie"][0]["value"], "REDACTED");
+    assert_eq!(
+        request["headers"]["host"][0]["value"],
+        "d111111abcdef8.cloudfront.net"
+    );
+    assert_eq!(request["querystring"], "name=rocket&query=REDACTED");
+    Ok(())
+}
+
+#[test]
+fn records_redacted_lattice_events() -> Result<(), Box<dyn Error>> {
+    let sink = Arc::new(InMemoryRecordingSink::default());
+    let mut handler = make_handler(sink.clone());
+
+    let mut event = get_event("lattice")?;
+    event["headers"]["authorization"] = serde_json::json!(["Bearer secret"]);
+    event["raw_path"] = "/upper/one?query=two&other=1".into();
+    handler.run(event, Context::default())?;
+
+    let invocation = &sink.invocations()[0];
+    assert_eq!(invocation.event["headers"]["authorization"][0], "REDACTED");
+    assert_eq!(invocation.event["headers"]["accept"][0], "*/*");
+    assert_eq!(
+        invocation.event["query_string_parameters"]["query"],
+        "REDACTED"
+    );
+    assert_eq!(
+        invocation.event["raw_path"],
+        "/upper/one?query=REDACTED&other=1"
+    );
+    Ok(())
+}
+
+#[test]
+fn records_bodies_when_enabled() -> Result<(), Box<dyn Error>> {
+    let sink = Arc::new(InMemoryRecordingSink::default());
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .record_events(sink.clone())
+        .record_bodies()
+        .into_handler();
+    let res = handler.run(get_authorized_event()?, Context::default())?;
+
+    let invocation = &sink.invocations()[0];
+    assert_eq!(invocation.event["body"], "three");
+    assert_eq!(invocation.result.as_ref().ok(), Some(&res));
+    Ok(())
+}
+
+#[test]
+fn records_errors() -> Result<(), Box<dyn Error>> {
+    let sink = Arc::new(InMemoryRecordingSink::default());
+    let mut handler = make_handler(sink.clone());
+
+    let direct = serde_json::json!({ "hello": "world" });
+    assert!(handler.run(direct.clone(), Context::default()).is_err());
+
+    let invocation = &sink.invocations()[0];
+    assert_eq!(invocation.event, direct);
+    let error = invocation.result.as_ref().unwrap_err();
+    assert!(
+        error.contains("no direct invocation route is configured"),
+        "{}",
+        error
+    );
+
+    // Replaying an invocation that failed matches if it fails in the same way.
+    assert!(invocation.replay(&mut handler).is_match());
+    Ok(())
+}
+
+#[test]
+fn replay_matching_responses() -> Result<(), Box<dyn Error>> {
+    let sink = Arc::new(InMemoryRecordingSink::default());
+    let mut handler = make_handler(sink.clone());
+    handler.run(get_authorized_event()?, Context::default())?;
+    handler.run(get_event("not_found")?, Context::default())?;
+
+    let mut replay_handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .into_handler();
+    for invocation in sink.invocations() {
+        let result = invocation.replay(&mut replay_handler);
+        assert!(result.is_match(), "{:?}", result.differences);
+    }
+    Ok(())
+}
+
+#[test]
+fn replay_reports_differences() -> Result<(), Box<dyn Error>> {
+    let sink = Arc::new(InMemoryRecordingSink::default());
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .record_events(sink.clone())
+        .record_bodies()
+        .into_handler();
+    handler.run(get_event("upper")?, Context::default())?;
+
+    let mut changed_handler = rocket::ignite()
+        .mount("/", routes![lower])
+        .lambda()
+        .into_handler();
+    let result = sink.invocations()[0].replay(&mut changed_handler);
+
+    assert!(!result.is_match());
+    assert_eq!(
+        result.differences,
+        vec![r#"$.body: expected "ONE THREE", got "one three""#.to_owned()]
+    );
+    Ok(())
+}
+
+#[test]
+fn directory_recordings_round_trip() -> Result<(), Box<dyn Error>> {
+    let directory =
+        std::env::temp_dir().join(format!("rocket_lamb_recordings_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+
+    let mut handler = rocket::ignite()
+        .mount("/", routes![upper])
+        .lambda()
+        .record_events(DirectoryRecordingSink::new(&directory))
+        .record_bodies()
+        .into_handler();
+    for (i, file) in ["upper", "not_found"].iter().enumerate() {
+        let mut ctx = Context::default();
+        ctx.aws_request_id = format!("request-{}", i);
+        handler.run(get_event(file)?, ctx)?;
+    }
+
+    let recordings = load_recordings(&directory)?;
+    std::fs::remove_dir_all(&directory)?;
+
+    assert_eq!(recordings.len(), 2);
+    assert_eq!(recordings[0].request_id, "request-0");
+    assert_eq!(recordings[1].request_id, "request-1");
+    assert_eq!(recordings[0].result.as_ref().unwrap()["body"], "ONE THREE");
+    assert_eq!(recordings[1].result.as_ref().unwrap()["statusCode"], 404);
+    Ok(())
+}
@@ -5,7 +5,8 @@ extern crate rocket;
 
 use lambda_http::{Body, Handler, Request, Response};
 use lambda_runtime::Context;
-use rocket_lamb::{ResponseType, RocketExt};
+use rocket::http::Method;
+use rocket_lamb::{ExtensionMethodBehaviour, OriginalMethod, ResponseType, RocketExt};
 use std::error::Error;
 use std::fs::File;
 
@@ -27,9 +28,14 @@ fn binary() -> &'static [u8] {
     &[200, 201, 202]
 }
 
+#[post("/fallback", data = "<body>")]
+fn fallback(method: OriginalMethod, body: String) -> String {
+    format!("{} {}", method.0, body)
+}
+
 fn make_rocket() -> rocket::Rocket {
     rocket::ignite()
-        .mount("/", routes![upper, binary])
+        .mount("/", routes![upper, binary, fallback])
         .register(catchers![not_found])
 }
 
@@ -115,6 +121,92 @@ fn request_not_found() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn head_falls_back_to_get() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let req = get_request("head_binary")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_header(&res, "content-type", "application/octet-stream");
+    assert_header(&res, "content-length", "3");
+    assert_eq!(*res.body(), Body::Empty);
+    Ok(())
+}
+
+#[test]
+fn extension_method_rejected() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let req = get_request("method_propfind")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 405);
+    assert_header(&res, "allow", "POST");
+    assert!(res.body().is_empty(), "Response body should be empty");
+    Ok(())
+}
+
+#[test]
+fn extension_method_mapped() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .extension_method("propfind", ExtensionMethodBehaviour::MapTo(Method::Post))
+        .into_handler();
+
+    let req = get_request("method_propfind")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("ONE, TWO, THREE".to_string()));
+    Ok(())
+}
+
+#[test]
+fn extension_method_fallback() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .default_extension_method(ExtensionMethodBehaviour::Fallback("/fallback".to_owned()))
+        .into_handler();
+
+    let req = get_request("method_propfind")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("PROPFIND three".to_string()));
+    Ok(())
+}
+
+#[test]
+fn extension_method_rejected_without_matching_routes() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let mut req = get_request("not_found")?;
+    *req.method_mut() = http::Method::from_bytes(b"PROPFIND")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 405);
+    assert!(res.headers().get("allow").is_none());
+    Ok(())
+}
+
+#[test]
+fn extension_method_fallback_with_base_path() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket()
+        .lambda()
+        .default_extension_method(ExtensionMethodBehaviour::Fallback("/fallback".to_owned()))
+        .into_handler();
+
+    let mut req = get_request("path_custom_domain_with_base")?;
+    *req.method_mut() = http::Method::from_bytes(b"PROPFIND")?;
+    let res = handler.run(req, Context::default())?;
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(*res.body(), Body::Text("PROPFIND ".to_string()));
+    Ok(())
+}
+
 fn assert_header(res: &Response<Body>, name: &str, value: &str) {
     let values = res.headers().get_all(name).iter().collect::<Vec<_>>();
     assert_eq!(values.len(), 1, "Header {} should have 1 value", name);
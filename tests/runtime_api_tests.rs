@@ -0,0 +1,132 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use rocket_lamb::testing::{EventBuilder, InvocationResult, MockRuntimeApi};
+use rocket_lamb::{RocketExt, RocketHandlerBuilder};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[get("/hello")]
+fn hello() -> &'static str {
+    "Hello, world!"
+}
+
+#[get("/panic")]
+fn panic() -> &'static str {
+    panic!("route panicked")
+}
+
+fn make_builder(cold_starts: Arc<AtomicUsize>) -> RocketHandlerBuilder {
+    rocket::ignite()
+        .mount("/", routes![hello, panic])
+        .lambda()
+        .on_cold_start(move |rocket, _ctx| {
+            cold_starts.fetch_add(1, Ordering::SeqCst);
+            rocket
+        })
+}
+
+fn launch(cold_starts: Arc<AtomicUsize>) -> MockRuntimeApi {
+    let api = MockRuntimeApi::start().unwrap();
+    api.launch(make_builder(cold_starts));
+    api
+}
+
+fn launch_http(cold_starts: Arc<AtomicUsize>) -> MockRuntimeApi {
+    let api = MockRuntimeApi::start().unwrap();
+    api.launch_http(make_builder(cold_starts));
+    api
+}
+
+#[test]
+fn sequential_invocations_share_cold_start() {
+    let cold_starts = Arc::new(AtomicUsize::new(0));
+    let api = launch(cold_starts.clone());
+    assert!(!api.has_exited());
+
+    for _ in 0..3 {
+        api.invoke(EventBuilder::rest("GET", "/hello").to_json())
+            .response()
+            .expect("the invocation should succeed")
+            .assert_status(200)
+            .assert_body("Hello, world!");
+    }
+    api.invoke(EventBuilder::http_api("GET", "/missing").to_json())
+        .response()
+        .expect("the invocation should succeed")
+        .assert_status(404);
+
+    assert_eq!(cold_starts.load(Ordering::SeqCst), 1);
+    assert_eq!(api.init_error(), None);
+}
+
+#[test]
+fn handler_errors_are_reported() {
+    let api = launch(Arc::default());
+
+    let result = api.invoke(json!({ "hello": "world" }));
+    match result {
+        InvocationResult::Error(error) => {
+            let message = error["errorMessage"].as_str().unwrap_or_default();
+            assert!(
+                message.contains("no direct invocation route is configured"),
+                "Unexpected error message: {}",
+                message
+            );
+        }
+        other => panic!("Expected an error, but got {:?}", other),
+    }
+
+    // The runtime keeps processing events after an error.
+    api.invoke(EventBuilder::alb("GET", "/hello").to_json())
+        .response()
+        .expect("the invocation should succeed")
+        .assert_body("Hello, world!");
+}
+
+#[test]
+fn panics_stop_the_runtime() {
+    let api = launch(Arc::default());
+
+    let result = api.invoke(EventBuilder::rest("GET", "/panic").to_json());
+    assert_eq!(result, InvocationResult::Exited);
+    assert!(api.has_exited());
+}
+
+#[test]
+fn http_launch_handles_requests() {
+    let cold_starts = Arc::new(AtomicUsize::new(0));
+    let api = launch_http(cold_starts.clone());
+    assert!(!api.has_exited());
+
+    for _ in 0..2 {
+        api.invoke(EventBuilder::rest("GET", "/hello").to_json())
+            .response()
+            .expect("the invocation should succeed")
+            .assert_status(200)
+            .assert_body("Hello, world!");
+    }
+    api.invoke(EventBuilder::alb("GET", "/missing").to_json())
+        .response()
+        .expect("the invocation should succeed")
+        .assert_status(404);
+
+    assert_eq!(cold_starts.load(Ordering::SeqCst), 1);
+    assert_eq!(api.init_error(), None);
+}
+
+#[test]
+fn http_launch_rejects_other_events() {
+    let api = launch_http(Arc::default());
+
+    // `lambda_http` cannot parse events that are not HTTP requests.
+    let result = api.invoke(json!({ "hello": "world" }));
+    assert!(
+        matches!(result, InvocationResult::Error(_)),
+        "Expected an error, but got {:?}",
+        result
+    );
+}
@@ -0,0 +1,226 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use rocket_lamb::{ResponseType, RocketExt, SamEventSource, SamRoutes};
+use serde_json::json;
+use std::path::PathBuf;
+
+#[get("/")]
+fn index() -> &'static str {
+    "index"
+}
+
+#[get("/items/<id>")]
+fn get_item(id: u32) -> String {
+    id.to_string()
+}
+
+#[delete("/items/<id>")]
+fn delete_item(id: u32) -> String {
+    id.to_string()
+}
+
+#[get("/items/<id>", rank = 2)]
+fn get_item_by_name(id: String) -> String {
+    id
+}
+
+#[get("/files/<path..>")]
+fn file(path: PathBuf) -> String {
+    path.display().to_string()
+}
+
+fn make_rocket() -> rocket::Rocket {
+    rocket::ignite()
+        .mount("/", routes![index, get_item, delete_item, get_item_by_name])
+        .mount("/static", routes![file])
+}
+
+#[test]
+fn template_function() {
+    let template =
+        make_rocket()
+            .lambda()
+            .sam_template("Api", SamEventSource::RestApi, SamRoutes::Mounted);
+
+    assert_eq!(template["Transform"], "AWS::Serverless-2016-10-31");
+    let function = &template["Resources"]["Api"];
+    assert_eq!(function["Type"], "AWS::Serverless::Function");
+    assert_eq!(function["Properties"]["Runtime"], "provided.al2");
+    assert_eq!(function["Properties"]["Handler"], "bootstrap");
+}
+
+#[test]
+fn rest_api_events_for_mounted_routes() {
+    let template =
+        make_rocket()
+            .lambda()
+            .sam_template("Fn", SamEventSource::RestApi, SamRoutes::Mounted);
+
+    let events = &template["Resources"]["Fn"]["Properties"]["Events"];
+    assert_eq!(
+        events,
+        &json!({
+            "GetRoot": {
+                "Type": "Api",
+                "Properties": { "RestApiId": { "Ref": "FnApi" }, "Path": "/", "Method": "get" },
+            },
+            "DeleteItemsId": {
+                "Type": "Api",
+                "Properties": { "RestApiId": { "Ref": "FnApi" }, "Path": "/items/{id}", "Method": "delete" },
+            },
+            "GetItemsId": {
+                "Type": "Api",
+                "Properties": { "RestApiId": { "Ref": "FnApi" }, "Path": "/items/{id}", "Method": "get" },
+            },
+            "GetStaticFiles": {
+                "Type": "Api",
+                "Properties": { "RestApiId": { "Ref": "FnApi" }, "Path": "/static/files", "Method": "get" },
+            },
+            "GetStaticFilesPath": {
+                "Type": "Api",
+                "Properties": {
+                    "RestApiId": { "Ref": "FnApi" },
+                    "Path": "/static/files/{path+}",
+                    "Method": "get",
+                },
+            },
+        })
+    );
+    assert_eq!(
+        template["Resources"]["FnApi"]["Type"],
+        "AWS::Serverless::Api"
+    );
+}
+
+#[test]
+fn rest_api_greedy_events() {
+    let template =
+        make_rocket()
+            .lambda()
+            .sam_template("Fn", SamEventSource::RestApi, SamRoutes::Greedy);
+
+    let events = &template["Resources"]["Fn"]["Properties"]["Events"];
+    assert_eq!(events.as_object().unwrap().len(), 2);
+    assert_eq!(events["Root"]["Properties"]["Path"], "/");
+    assert_eq!(events["Proxy"]["Properties"]["Path"], "/{proxy+}");
+    assert_eq!(events["Proxy"]["Properties"]["Method"], "any");
+}
+
+#[test]
+fn rest_api_binary_media_types() {
+    let template = make_rocket()
+        .lambda()
+        .response_type("image/png", ResponseType::Binary)
+        .response_type("application/pdf", ResponseType::Binary)
+        .response_type("text/plain", ResponseType::Text)
+        .sam_template("Fn", SamEventSource::RestApi, SamRoutes::Greedy);
+
+    assert_eq!(
+        template["Resources"]["FnApi"]["Properties"]["BinaryMediaTypes"],
+        json!(["application~1pdf", "image~1png"])
+    );
+}
+
+#[test]
+fn rest_api_without_binary_media_types() {
+    let template =
+        make_rocket()
+            .lambda()
+            .sam_template("Fn", SamEventSource::RestApi, SamRoutes::Greedy);
+
+    assert!(template["Resources"]["FnApi"]["Properties"]
+        .get("BinaryMediaTypes")
+        .is_none());
+}
+
+#[test]
+fn http_api_events_for_mounted_routes() {
+    let template =
+        make_rocket()
+            .lambda()
+            .sam_template("Fn", SamEventSource::HttpApi, SamRoutes::Mounted);
+
+    let events = &template["Resources"]["Fn"]["Properties"]["Events"];
+    assert_eq!(
+        events["GetItemsId"],
+        json!({
+            "Type": "HttpApi",
+            "Properties": { "Path": "/items/{id}", "Method": "get" },
+        })
+    );
+    assert_eq!(events.as_object().unwrap().len(), 5);
+    assert!(template["Resources"].get("FnApi").is_none());
+}
+
+#[test]
+fn http_api_greedy_default_route() {
+    let template =
+        make_rocket()
+            .lambda()
+            .sam_template("Fn", SamEventSource::HttpApi, SamRoutes::Greedy);
+
+    assert_eq!(
+        template["Resources"]["Fn"]["Properties"]["Events"],
+        json!({ "Default": { "Type": "HttpApi" } })
+    );
+}
+
+#[test]
+fn alb_target_group_and_rules() {
+    let template = make_rocket().lambda().sam_template(
+        "Fn",
+        SamEventSource::Alb { base_priority: 100 },
+        SamRoutes::Mounted,
+    );
+
+    assert_eq!(template["Parameters"]["ListenerArn"]["Type"], "String");
+    let resources = &template["Resources"];
+    assert!(resources["Fn"]["Properties"].get("Events").is_none());
+    assert_eq!(
+        resources["FnTargetGroup"]["Properties"]["TargetType"],
+        "lambda"
+    );
+    assert_eq!(
+        resources["FnTargetGroup"]["DependsOn"],
+        "FnLoadBalancerPermission"
+    );
+    assert_eq!(
+        resources["FnLoadBalancerPermission"]["Properties"]["Principal"],
+        "elasticloadbalancing.amazonaws.com"
+    );
+
+    assert_eq!(resources["FnGetRootRule"]["Properties"]["Priority"], 100);
+    let rule = &resources["FnGetItemsIdRule"]["Properties"];
+    assert_eq!(rule["Priority"], 102);
+    assert_eq!(rule["ListenerArn"], json!({ "Ref": "ListenerArn" }));
+    assert_eq!(
+        rule["Conditions"],
+        json!([
+            { "Field": "path-pattern", "PathPatternConfig": { "Values": ["/items/*"] } },
+            { "Field": "http-request-method", "HttpRequestMethodConfig": { "Values": ["GET"] } },
+        ])
+    );
+    assert_eq!(
+        rule["Actions"][0]["TargetGroupArn"],
+        json!({ "Ref": "FnTargetGroup" })
+    );
+}
+
+#[test]
+fn alb_greedy_rule() {
+    let template = make_rocket().lambda().sam_template(
+        "Fn",
+        SamEventSource::Alb { base_priority: 1 },
+        SamRoutes::Greedy,
+    );
+
+    let rule = &template["Resources"]["FnProxyRule"]["Properties"];
+    assert_eq!(rule["Priority"], 1);
+    assert_eq!(
+        rule["Conditions"],
+        json!([{ "Field": "path-pattern", "PathPatternConfig": { "Values": ["/*"] } }])
+    );
+}
@@ -0,0 +1,166 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_http::request::RequestContext;
+use lambda_http::{Handler, RequestExt};
+use rocket::http::uri::Origin;
+use rocket_lamb::testing::{mock_context, EventBuilder, TestResponse};
+use rocket_lamb::{PathParameters, RocketExt, StageVariables};
+
+#[post("/upper/<path>", data = "<body>")]
+fn upper(path: String, body: String) -> String {
+    format!("{} {}", path, body).to_uppercase()
+}
+
+#[get("/path")]
+fn get_path<'r>(origin: &'r Origin<'r>) -> String {
+    origin.to_string()
+}
+
+#[get("/params")]
+fn params(params: PathParameters, variables: StageVariables) -> String {
+    format!(
+        "{}, {}",
+        params.get("proxy").unwrap_or_default(),
+        variables.get("table").unwrap_or_default()
+    )
+}
+
+#[get("/binary")]
+fn binary() -> Vec<u8> {
+    vec![0, 159, 146, 150]
+}
+
+fn make_handler() -> rocket_lamb::RocketHandler {
+    rocket::ignite()
+        .mount("/", routes![upper, get_path, params, binary])
+        .lambda()
+        .into_handler()
+}
+
+#[test]
+fn rest_event() {
+    let mut handler = make_handler();
+    EventBuilder::rest("POST", "/upper/one")
+        .header("content-type", "text/plain")
+        .body("two")
+        .run(&mut handler)
+        .assert_status(200)
+        .assert_header("content-type", "text/plain; charset=utf-8")
+        .assert_body("ONE TWO");
+}
+
+#[test]
+fn http_api_event_with_query() {
+    let mut handler = make_handler();
+    EventBuilder::http_api("GET", "/path")
+        .query("a", "x y")
+        .query("flag", "")
+        .run(&mut handler)
+        .assert_status(200)
+        .assert_body("/path?a=x%20y&flag");
+}
+
+#[test]
+fn http_api_event_with_named_stage() {
+    let mut handler = make_handler();
+    EventBuilder::http_api("GET", "/path")
+        .stage("Prod")
+        .run(&mut handler)
+        .assert_body("/Prod/path");
+}
+
+#[test]
+fn alb_event() {
+    let mut handler = make_handler();
+    EventBuilder::alb("GET", "/path")
+        .query("a", "1")
+        .run(&mut handler)
+        .assert_status(200)
+        .assert_body("/path?a=1");
+}
+
+#[test]
+fn custom_domain_event() {
+    let mut handler = make_handler();
+    EventBuilder::custom_domain("GET", "/path", "base")
+        .run(&mut handler)
+        .assert_status(200)
+        .assert_body("/base/path");
+}
+
+#[test]
+fn binary_request_and_response() {
+    let mut handler = make_handler();
+    EventBuilder::rest("POST", "/upper/one")
+        .binary_body(b"two")
+        .run(&mut handler)
+        .assert_body("ONE TWO");
+
+    let res = EventBuilder::rest("GET", "/binary").run(&mut handler);
+    res.assert_status(200).assert_body([0, 159, 146, 150]);
+    assert_eq!(res.header("Content-Type"), Some("application/octet-stream"));
+}
+
+#[test]
+fn path_parameters_and_stage_variables() {
+    let mut handler = make_handler();
+    EventBuilder::rest("GET", "/params")
+        .stage_variable("table", "users")
+        .run(&mut handler)
+        .assert_body("params, users");
+}
+
+#[test]
+fn not_found() {
+    let mut handler = make_handler();
+    EventBuilder::rest("GET", "/missing")
+        .run(&mut handler)
+        .assert_status(404)
+        .assert_no_header("location");
+}
+
+#[test]
+fn authorizer_claims() {
+    let req = EventBuilder::rest("GET", "/path")
+        .authorizer_claim("sub", "user-1")
+        .build();
+    match req.request_context() {
+        RequestContext::ApiGateway { authorizer, .. } => {
+            assert_eq!(authorizer["claims"]["sub"], "user-1");
+        }
+        RequestContext::Alb { .. } => panic!("Expected an API Gateway request context"),
+    }
+
+    let req = EventBuilder::http_api("GET", "/path")
+        .authorizer_claim("sub", "user-1")
+        .build();
+    match req.request_context() {
+        RequestContext::ApiGateway { authorizer, .. } => {
+            assert_eq!(authorizer["jwt"]["claims"]["sub"], "user-1");
+        }
+        RequestContext::Alb { .. } => panic!("Expected an API Gateway request context"),
+    }
+}
+
+#[test]
+fn built_request_through_http_handler() {
+    let mut handler = make_handler();
+    let req = EventBuilder::http_api("POST", "/upper/one")
+        .header("content-type", "text/plain")
+        .body("two")
+        .build();
+    let res = handler.run(req, mock_context()).unwrap();
+
+    TestResponse::from(res)
+        .assert_status(200)
+        .assert_body("ONE TWO");
+}
+
+#[test]
+#[should_panic(expected = "Response has no valid statusCode")]
+fn response_without_status_code() {
+    TestResponse::from_json(&serde_json::json!({ "body": "hello" }));
+}
@@ -0,0 +1,109 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_runtime::{Context, Handler};
+use rocket::http::uri::Origin;
+use rocket::State;
+use rocket_lamb::{InMemoryConnectionClient, RocketExt, WebSocketConnection};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs::File;
+use std::sync::Arc;
+
+#[post("/$connect?<name>")]
+fn connect(connection: WebSocketConnection, name: String) -> String {
+    format!(
+        "{} {} {} {}",
+        name, connection.event_type, connection.connection_id, connection.stage
+    )
+}
+
+#[post("/$disconnect")]
+fn disconnect<'r>(origin: &'r Origin<'r>) -> &'r str {
+    origin.query().unwrap_or_default()
+}
+
+#[post("/sendMessage", data = "<body>")]
+fn send_message(
+    connection: WebSocketConnection,
+    client: State<Arc<InMemoryConnectionClient>>,
+    body: String,
+) -> Option<()> {
+    connection
+        .post(client.as_ref(), body.to_uppercase().as_bytes())
+        .ok()
+}
+
+fn make_rocket() -> rocket::Rocket {
+    make_rocket_with_client(Arc::default())
+}
+
+fn make_rocket_with_client(client: Arc<InMemoryConnectionClient>) -> rocket::Rocket {
+    rocket::ignite()
+        .mount("/", routes![connect, disconnect, send_message])
+        .manage(client)
+}
+
+fn get_event(json_file: &str) -> Result<Value, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+#[test]
+fn websocket_connect() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let event = get_event("websocket_connect")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["body"], "Rocket CONNECT a1b2c3d4e5f6g7h= Prod");
+    assert_eq!(res["isBase64Encoded"], false);
+    Ok(())
+}
+
+#[test]
+fn websocket_post_to_connection() -> Result<(), Box<dyn Error>> {
+    let client = Arc::new(InMemoryConnectionClient::default());
+    let mut handler = make_rocket_with_client(client.clone())
+        .lambda()
+        .into_handler();
+
+    let event = get_event("websocket_message")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["body"], Value::Null);
+    assert_eq!(client.messages("a1b2c3d4e5f6g7h="), vec![b"HELLO".to_vec()]);
+    Ok(())
+}
+
+#[test]
+fn http_request_as_raw_event() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let event = get_event("not_found")?;
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 404);
+    Ok(())
+}
+
+#[test]
+fn websocket_query_from_multi_value_parameters() -> Result<(), Box<dyn Error>> {
+    let mut handler = make_rocket().lambda().into_handler();
+
+    let mut event = get_event("websocket_connect")?;
+    event["requestContext"]["routeKey"] = json!("$disconnect");
+    event["requestContext"]["eventType"] = json!("DISCONNECT");
+    event["queryStringParameters"] = json!({ "b": "3", "a": "x y", "flag": "" });
+    event["multiValueQueryStringParameters"] =
+        json!({ "b": ["2", "3"], "a": ["x y"], "flag": [""] });
+    let res = handler.run(event, Context::default())?;
+
+    assert_eq!(res["statusCode"], 200);
+    assert_eq!(res["body"], "a=x%20y&b=2&b=3&flag");
+    Ok(())
+}
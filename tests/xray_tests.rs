@@ -0,0 +1,77 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use lambda_http::{Body, Handler, Request};
+use lambda_runtime::Context;
+use rocket_lamb::{RocketExt, TraceContext};
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+const TRACE_ID: &str = "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1";
+
+#[post("/upper/<_path>")]
+fn trace(_path: String, trace: TraceContext) -> String {
+    trace.root
+}
+
+fn get_request(json_file: &str) -> Result<Request, Box<dyn Error>> {
+    let file = File::open(format!("tests/requests/{}.json", json_file))?;
+    Ok(lambda_http::request::from_reader(file)?)
+}
+
+fn context() -> Context {
+    let mut ctx = Context::default();
+    ctx.xray_trace_id = Some(TRACE_ID.to_owned());
+    ctx
+}
+
+#[test]
+fn trace_context_guard() -> Result<(), Box<dyn Error>> {
+    let mut handler = rocket::ignite()
+        .mount("/", routes![trace])
+        .lambda()
+        .into_handler();
+
+    let res = handler.run(get_request("upper")?, context())?;
+
+    assert_eq!(
+        *res.body(),
+        Body::Text("1-5759e988-bd862e3fe1be46a994272793".to_string())
+    );
+    Ok(())
+}
+
+#[test]
+fn sends_subsegment_to_daemon() -> Result<(), Box<dyn Error>> {
+    let daemon = UdpSocket::bind("127.0.0.1:0")?;
+    daemon.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut handler = rocket::ignite()
+        .mount("/", routes![trace])
+        .lambda()
+        .xray_daemon_address(&daemon.local_addr()?.to_string())
+        .into_handler();
+
+    handler.run(get_request("upper")?, context())?;
+
+    let mut buf = [0; 65536];
+    let (len, _) = daemon.recv_from(&mut buf)?;
+    let packet = String::from_utf8_lossy(&buf[..len]).into_owned();
+    let mut lines = packet.lines();
+    assert_eq!(lines.next(), Some(r#"{"format": "json", "version": 1}"#));
+    let segment: Value = serde_json::from_str(lines.next().unwrap())?;
+    assert_eq!(segment["type"], "subsegment");
+    assert_eq!(segment["trace_id"], "1-5759e988-bd862e3fe1be46a994272793");
+    assert_eq!(segment["parent_id"], "53995c3f42cd8ad8");
+    assert_eq!(segment["name"], "trace");
+    assert_eq!(segment["annotations"]["route"], "POST /upper/<_path>");
+    assert_eq!(segment["http"]["response"]["status"], 200);
+    assert_eq!(segment["fault"], false);
+    assert_eq!(segment["error"], false);
+    assert_eq!(segment["id"].as_str().map(str::len), Some(16));
+    Ok(())
+}